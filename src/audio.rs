@@ -0,0 +1,295 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
+use symphonia::core::{
+    audio::SampleBuffer, codecs::DecoderOptions, errors::Error as DecodeError,
+    formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
+use thiserror::Error;
+
+use crate::{error::Error, manifest::ManifestAudio};
+
+/// Samples per fft window, giving half as many spectrum bins.
+const FFT_SIZE: usize = 1024;
+/// Width of the audio texture, each texel holds a spectrum bin and a waveform sample.
+pub const AUDIO_BINS: usize = FFT_SIZE / 2;
+const MIN_DB: f32 = -100.0;
+const MAX_DB: f32 = -30.0;
+
+/// A decoded audio file, kept in memory as interleaved f32 samples.
+#[derive(Debug)]
+pub struct AudioClip {
+    pub sample_rate: u32,
+    pub channels: usize,
+    pub samples: Vec<f32>,
+}
+
+impl AudioClip {
+    pub fn load(path: &Path) -> Result<Self, AudioError> {
+        let file = fs::File::open(path)?;
+        let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+            hint.with_extension(extension);
+        }
+
+        let probed = symphonia::default::get_probe().format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?;
+        let mut format = probed.format;
+
+        let track = format
+            .default_track()
+            .ok_or_else(|| AudioError::NoTrack(path.to_path_buf()))?;
+        let track_id = track.id;
+        let sample_rate = track
+            .codec_params
+            .sample_rate
+            .ok_or_else(|| AudioError::NoTrack(path.to_path_buf()))?;
+
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())?;
+
+        let mut channels = 1;
+        let mut samples = Vec::new();
+
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(DecodeError::IoError(err)) if err.kind() == io::ErrorKind::UnexpectedEof => {
+                    break
+                }
+                Err(DecodeError::ResetRequired) => break,
+                Err(err) => return Err(err.into()),
+            };
+
+            if packet.track_id() != track_id {
+                continue;
+            }
+
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(DecodeError::DecodeError(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            let spec = *decoded.spec();
+            channels = spec.channels.count().max(1);
+
+            let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+            buffer.copy_interleaved_ref(decoded);
+            samples.extend_from_slice(buffer.samples());
+        }
+
+        Ok(Self {
+            sample_rate,
+            channels,
+            samples,
+        })
+    }
+
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels
+    }
+
+    /// Channels of `frame` mixed down to mono, silent outside of the clip.
+    fn mono(&self, frame: i64) -> f32 {
+        if frame < 0 || frame as usize >= self.frames() {
+            return 0.0;
+        }
+
+        let start = frame as usize * self.channels;
+        let frame = &self.samples[start..start + self.channels];
+        frame.iter().sum::<f32>() / self.channels as f32
+    }
+
+    /// Spectrum and waveform of the window ending at `time`.
+    ///
+    /// Only depends on `time`, so headless renders of a frame are reproducible.
+    pub fn analyze(&self, fft: &dyn Fft<f32>, time: f32) -> Vec<[f32; 2]> {
+        let end = (time as f64 * self.sample_rate as f64) as i64;
+        let start = end - FFT_SIZE as i64;
+
+        let mut buffer = (0..FFT_SIZE)
+            .map(|i| {
+                let hann = 0.5 - 0.5 * (std::f32::consts::TAU * i as f32 / FFT_SIZE as f32).cos();
+                Complex::new(self.mono(start + i as i64) * hann, 0.0)
+            })
+            .collect::<Vec<_>>();
+        fft.process(&mut buffer);
+
+        (0..AUDIO_BINS)
+            .map(|bin| {
+                // the hann window halves the amplitude, hence 4 rather than 2
+                let magnitude = buffer[bin].norm() * 4.0 / FFT_SIZE as f32;
+                let db = 20.0 * magnitude.max(1e-10).log10();
+                let spectrum = ((db - MIN_DB) / (MAX_DB - MIN_DB)).clamp(0.0, 1.0);
+
+                let waveform = self.mono(end - AUDIO_BINS as i64 + bin as i64);
+
+                [spectrum, waveform]
+            })
+            .collect()
+    }
+}
+
+/// The manifest's audio track and the 1d texture its spectrum is uploaded to.
+///
+/// Bound as group 2 of every shader, see `kiln/audio`. Without an audio track
+/// the texture is silent.
+pub struct Audio {
+    pub manifest: Option<ManifestAudio>,
+    pub clip: Option<Arc<AudioClip>>,
+    fft: Arc<dyn Fft<f32>>,
+    texture: wgpu::Texture,
+    pub layout: wgpu::BindGroupLayout,
+    pub group: wgpu::BindGroup,
+}
+
+impl Audio {
+    pub fn new(
+        device: &wgpu::Device,
+        root: &Path,
+        manifest: Option<&ManifestAudio>,
+    ) -> Result<Self, Error> {
+        let clip = match manifest {
+            Some(manifest) => {
+                let path = root.join(&manifest.file);
+
+                if !path.exists() {
+                    return Err(Error::InvalidPath(path));
+                }
+
+                Some(Arc::new(AudioClip::load(&path)?))
+            }
+            None => None,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("kiln-audio-texture"),
+            size: wgpu::Extent3d {
+                width: AUDIO_BINS as u32,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D1,
+            format: wgpu::TextureFormat::Rg32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+        });
+        let view = texture.create_view(&Default::default());
+
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("kiln-audio-layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D1,
+                    multisampled: false,
+                },
+                count: None,
+            }],
+        });
+
+        let group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("kiln-audio-group"),
+            layout: &layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::TextureView(&view),
+            }],
+        });
+
+        Ok(Self {
+            manifest: manifest.cloned(),
+            clip,
+            fft: FftPlanner::new().plan_fft_forward(FFT_SIZE),
+            texture,
+            layout,
+            group,
+        })
+    }
+
+    /// Uploads the spectrum and waveform at `time`.
+    pub fn write(&self, queue: &wgpu::Queue, time: f32) {
+        let texels = match self.clip {
+            Some(ref clip) => clip.analyze(self.fft.as_ref(), time),
+            None => vec![[0.0; 2]; AUDIO_BINS],
+        };
+
+        queue.write_texture(
+            self.texture.as_image_copy(),
+            bytemuck::cast_slice(&texels),
+            wgpu::ImageDataLayout {
+                offset: 0,
+                bytes_per_row: None,
+                rows_per_image: None,
+            },
+            wgpu::Extent3d {
+                width: AUDIO_BINS as u32,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+        );
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AudioError {
+    #[error("'{0}' has no playable audio track")]
+    NoTrack(PathBuf),
+    #[error("failed to decode audio: {0}")]
+    Decode(#[from] DecodeError),
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spectrum_peaks_at_tone() {
+        let sample_rate = 48000;
+        let frequency = 3000.0;
+        let samples = (0..sample_rate)
+            .map(|i| {
+                let phase = std::f32::consts::TAU * frequency * i as f32 / sample_rate as f32;
+                0.01 * phase.sin()
+            })
+            .collect();
+
+        let clip = AudioClip {
+            sample_rate,
+            channels: 1,
+            samples,
+        };
+
+        let fft = FftPlanner::new().plan_fft_forward(FFT_SIZE);
+        let texels = clip.analyze(fft.as_ref(), 0.5);
+        assert_eq!(texels, clip.analyze(fft.as_ref(), 0.5));
+
+        let peak = (0..AUDIO_BINS)
+            .max_by(|&a, &b| texels[a][0].total_cmp(&texels[b][0]))
+            .unwrap();
+        let expected = frequency as usize * FFT_SIZE / sample_rate as usize;
+        assert_eq!(peak, expected);
+
+        // before the clip starts everything is silent
+        assert!(clip
+            .analyze(fft.as_ref(), 0.0)
+            .iter()
+            .all(|&texel| texel == [0.0, 0.0]));
+    }
+}
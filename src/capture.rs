@@ -0,0 +1,186 @@
+use std::{path::Path, sync::mpsc};
+
+use image::{DynamicImage, ImageBuffer, Rgba};
+
+use crate::error::{Error, Result};
+
+pub fn create_target(
+    device: &wgpu::Device,
+    width: u32,
+    height: u32,
+    format: wgpu::TextureFormat,
+) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("kiln-capture-target"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format,
+        usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+    })
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// A frame read back from the gpu.
+///
+/// 8 bit images hold srgb encoded values while float images are linear.
+pub struct Capture {
+    pub image: DynamicImage,
+}
+
+impl Capture {
+    /// Copies `texture` into a mappable buffer and waits for it to be read back.
+    pub fn read(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        texture: &wgpu::Texture,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Result<Self> {
+        let pixel_size = match format {
+            wgpu::TextureFormat::Rgba8Unorm
+            | wgpu::TextureFormat::Rgba8UnormSrgb
+            | wgpu::TextureFormat::Bgra8Unorm
+            | wgpu::TextureFormat::Bgra8UnormSrgb => 4,
+            wgpu::TextureFormat::Rgba16Float => 8,
+            _ => return Err(Error::UnsupportedFormat(format)),
+        };
+
+        let row_size = width * pixel_size;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+        let padded_row_size = row_size.div_ceil(align) * align;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("kiln-capture-buffer"),
+            size: padded_row_size as u64 * height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::ImageCopyBuffer {
+                buffer: &buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: std::num::NonZeroU32::new(padded_row_size),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        queue.submit(std::iter::once(encoder.finish()));
+
+        let slice = buffer.slice(..);
+        let (sender, receiver) = mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        device.poll(wgpu::Maintain::Wait);
+        receiver.recv().expect("map callback dropped")?;
+
+        let data = slice.get_mapped_range();
+        let rows = data
+            .chunks(padded_row_size as usize)
+            .flat_map(|row| &row[..row_size as usize]);
+
+        let image = match format {
+            wgpu::TextureFormat::Rgba16Float => {
+                let pixels: Vec<u8> = rows.copied().collect();
+                let pixels = pixels
+                    .chunks(2)
+                    .map(|half| half::f16::from_le_bytes([half[0], half[1]]).to_f32())
+                    .collect();
+
+                DynamicImage::ImageRgba32F(ImageBuffer::from_raw(width, height, pixels).unwrap())
+            }
+            wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb => {
+                let mut pixels: Vec<u8> = rows.copied().collect();
+                for pixel in pixels.chunks_mut(4) {
+                    pixel.swap(0, 2);
+                }
+
+                DynamicImage::ImageRgba8(ImageBuffer::from_raw(width, height, pixels).unwrap())
+            }
+            _ => {
+                let pixels = rows.copied().collect();
+                DynamicImage::ImageRgba8(ImageBuffer::from_raw(width, height, pixels).unwrap())
+            }
+        };
+
+        drop(data);
+        buffer.unmap();
+
+        Ok(Self { image })
+    }
+
+    /// Saves the capture, picking the encoding from the extension of `path`.
+    ///
+    /// Exr files are written as linear floats, everything else as srgb.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let is_exr = path
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("exr"));
+
+        match (&self.image, is_exr) {
+            (DynamicImage::ImageRgba32F(image), false) => {
+                let mut encoded = ImageBuffer::<Rgba<u8>, _>::new(image.width(), image.height());
+
+                for (pixel, encoded) in image.pixels().zip(encoded.pixels_mut()) {
+                    let [r, g, b, a] = pixel.0;
+                    let quantize = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+                    encoded.0 = [
+                        quantize(linear_to_srgb(r)),
+                        quantize(linear_to_srgb(g)),
+                        quantize(linear_to_srgb(b)),
+                        quantize(a),
+                    ];
+                }
+
+                encoded.save(path)?;
+            }
+            (DynamicImage::ImageRgba8(image), true) => {
+                let mut decoded = ImageBuffer::<Rgba<f32>, _>::new(image.width(), image.height());
+
+                for (pixel, decoded) in image.pixels().zip(decoded.pixels_mut()) {
+                    let [r, g, b, a] = pixel.0.map(|value| value as f32 / 255.0);
+                    decoded.0 = [srgb_to_linear(r), srgb_to_linear(g), srgb_to_linear(b), a];
+                }
+
+                decoded.save(path)?;
+            }
+            (image, _) => image.save(path)?,
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,60 @@
+use clap::{crate_authors, crate_version, Parser};
+
+use kiln::{
+    error::{Error, Result},
+    shader::{ShaderEntry, ShaderKind},
+    shader_processor::ShaderProcessor,
+};
+
+use crate::cli::project::ProjectArgs;
+
+#[derive(Parser)]
+#[clap(version = crate_version!(), author = crate_authors!())]
+pub struct Check {
+    #[clap(flatten)]
+    pub project: ProjectArgs,
+}
+
+impl Check {
+    fn check_entry(processor: &mut ShaderProcessor, entry: ShaderEntry<'_>) -> Result<()> {
+        let source = entry.source(processor)?;
+        let module = entry.parse(&source)?;
+
+        let entry_points = module
+            .entry_points
+            .iter()
+            .map(|entry| format!("{} ({:?})", entry.name, entry.stage))
+            .collect::<Vec<_>>();
+
+        println!("  {}: {}", entry.display_path(), entry_points.join(", "));
+
+        entry.verify(&module)?;
+
+        Ok(())
+    }
+
+    pub fn run(self) -> Result<()> {
+        let mut project = self.project.load()?;
+        project.update_processor()?;
+        let mut errors = 0;
+
+        for (name, shader) in project.manifest.shaders.iter() {
+            println!("{}:", name);
+
+            let kind = ShaderKind::from_manifest(&project.path, name, shader);
+
+            for entry in kind.entries() {
+                if let Err(err) = Self::check_entry(&mut project.processor, entry) {
+                    println!("  error: {}", err);
+                    errors += 1;
+                }
+            }
+        }
+
+        if errors > 0 {
+            return Err(Error::Check(errors));
+        }
+
+        Ok(())
+    }
+}
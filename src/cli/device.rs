@@ -0,0 +1,48 @@
+use clap::Args;
+use kiln::{
+    device::{parse_backends, parse_feature, DeviceOptions},
+    manifest::{ManifestAdapter, ManifestDevice},
+    Result,
+};
+
+/// Device selection flags, overriding the `[device]` table of the manifest.
+#[derive(Args)]
+pub struct DeviceArgs {
+    /// Comma separated list of backends, eg. vulkan, gl, metal or dx12.
+    #[clap(long)]
+    pub backend: Option<String>,
+    /// Adapter index, as listed by `kiln info`, or part of its name.
+    #[clap(long)]
+    pub adapter: Option<String>,
+    /// Force the fallback (software) adapter, also when choosing one with `--adapter`.
+    #[clap(long)]
+    pub fallback_adapter: bool,
+    /// Device feature the project needs, eg. shader-float64, can be repeated.
+    #[clap(long = "feature", value_name = "NAME")]
+    pub features: Vec<String>,
+}
+
+impl DeviceArgs {
+    pub fn options(&self, manifest: &ManifestDevice) -> Result<DeviceOptions> {
+        let mut options = DeviceOptions::from_manifest(manifest)?;
+
+        if let Some(ref backend) = self.backend {
+            options.backends = parse_backends(backend)?;
+        }
+
+        if let Some(ref adapter) = self.adapter {
+            options.adapter = Some(match adapter.parse() {
+                Ok(index) => ManifestAdapter::Index(index),
+                Err(_) => ManifestAdapter::Name(adapter.clone()),
+            });
+        }
+
+        options.force_fallback_adapter |= self.fallback_adapter;
+
+        for feature in self.features.iter() {
+            options.required_features |= parse_feature(feature)?;
+        }
+
+        Ok(options)
+    }
+}
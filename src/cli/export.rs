@@ -0,0 +1,61 @@
+use std::path::PathBuf;
+
+use clap::{crate_authors, crate_version, ArgEnum, Parser};
+
+use kiln::{Headless, Result};
+
+use crate::cli::{device::DeviceArgs, project::ProjectArgs};
+
+#[derive(Clone, Copy, Debug, ArgEnum)]
+pub enum ExportFormat {
+    Rgba8,
+    Rgba16f,
+}
+
+impl ExportFormat {
+    pub fn texture_format(self) -> wgpu::TextureFormat {
+        match self {
+            ExportFormat::Rgba8 => wgpu::TextureFormat::Rgba8UnormSrgb,
+            ExportFormat::Rgba16f => wgpu::TextureFormat::Rgba16Float,
+        }
+    }
+}
+
+#[derive(Parser)]
+#[clap(version = crate_version!(), author = crate_authors!())]
+pub struct Export {
+    #[clap(flatten)]
+    pub project: ProjectArgs,
+    /// Output image, encoded according to its extension.
+    #[clap(short, long, default_value = "kiln.png")]
+    pub output: PathBuf,
+    #[clap(long, default_value_t = 1920)]
+    pub width: u32,
+    #[clap(long, default_value_t = 1080)]
+    pub height: u32,
+    /// Time in seconds passed to the shaders.
+    #[clap(short, long, default_value_t = 0.0)]
+    pub time: f32,
+    /// Format of the render target, use rgba16f for exr output.
+    #[clap(long, arg_enum, default_value = "rgba8")]
+    pub format: ExportFormat,
+    #[clap(flatten)]
+    pub device: DeviceArgs,
+}
+
+impl Export {
+    pub fn run(self) -> Result<()> {
+        let project = self.project.load()?;
+        let device = self.device.options(&project.manifest.device)?;
+
+        let mut headless = Headless::builder(project)
+            .device(device)
+            .format(self.format.texture_format())
+            .build()?;
+
+        let capture = headless.render(self.width, self.height, self.time as f64)?;
+        capture.save(&self.output)?;
+
+        Ok(())
+    }
+}
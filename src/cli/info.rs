@@ -0,0 +1,39 @@
+use clap::{crate_authors, crate_version, Parser};
+
+use kiln::{device, error::Result};
+
+#[derive(Parser)]
+#[clap(version = crate_version!(), author = crate_authors!())]
+pub struct Info {
+    /// Comma separated list of backends to list adapters for.
+    #[clap(long, default_value = "all")]
+    pub backend: String,
+}
+
+impl Info {
+    pub fn run(self) -> Result<()> {
+        let backends = device::parse_backends(&self.backend)?;
+        let instance = wgpu::Instance::new(backends);
+
+        for (index, adapter) in instance.enumerate_adapters(backends).enumerate() {
+            let info = adapter.get_info();
+
+            println!(
+                "{}: {} ({:?}, {:?})",
+                index, info.name, info.backend, info.device_type
+            );
+
+            let features = device::feature_names(adapter.features());
+            println!("  features: {}", features.join(", "));
+
+            println!("  limits:");
+            for line in format!("{:#?}", adapter.limits()).lines().skip(1) {
+                if line != "}" {
+                    println!("    {}", line.trim().trim_end_matches(','));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+
+use clap::{crate_authors, crate_version, Parser};
+
+use kiln::{
+    dependency::{Dependency, Lockfile},
+    error::Result,
+    manifest::Manifest,
+};
+
+#[derive(Parser)]
+#[clap(version = crate_version!(), author = crate_authors!())]
+pub struct Lock {
+    #[clap(default_value = ".")]
+    pub path: PathBuf,
+}
+
+impl Lock {
+    pub fn run(self) -> Result<()> {
+        let manifest = Manifest::load(&Manifest::find(&self.path))?;
+        let dependencies = Dependency::resolve_all(&self.path, &manifest)?;
+
+        for dependency in dependencies.iter() {
+            println!(
+                "{} {} ({})",
+                dependency.name,
+                &dependency.hash[..12],
+                dependency.path.display()
+            );
+        }
+
+        Lockfile::new(&dependencies).save(&self.path)
+    }
+}
@@ -0,0 +1,113 @@
+use std::{fs, path::PathBuf};
+
+use clap::{crate_authors, crate_version, ArgEnum, Parser, Subcommand};
+
+use kiln::{
+    error::Result,
+    manifest::{Manifest, ManifestFormat},
+};
+
+#[derive(Subcommand)]
+enum ManifestSubcommand {
+    /// Print the JSON schema of Kiln.toml, for editor completion.
+    Schema(Schema),
+    /// Convert a manifest between toml, json and ron.
+    Convert(Convert),
+}
+
+#[derive(Parser)]
+#[clap(version = crate_version!(), author = crate_authors!())]
+pub struct ManifestCommand {
+    #[clap(subcommand)]
+    command: ManifestSubcommand,
+}
+
+impl ManifestCommand {
+    pub fn run(self) -> Result<()> {
+        match self.command {
+            ManifestSubcommand::Schema(schema) => schema.run(),
+            ManifestSubcommand::Convert(convert) => convert.run(),
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct Schema {
+    /// Write the schema to a file instead of stdout.
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+impl Schema {
+    pub fn run(self) -> Result<()> {
+        let schema = serde_json::to_string_pretty(&Manifest::schema())
+            .expect("manifest schema serializes to json");
+
+        match self.output {
+            Some(path) => fs::write(path, schema + "\n")?,
+            None => println!("{}", schema),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, ArgEnum)]
+pub enum Format {
+    Toml,
+    Json,
+    Ron,
+}
+
+impl From<Format> for ManifestFormat {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Toml => ManifestFormat::Toml,
+            Format::Json => ManifestFormat::Json,
+            Format::Ron => ManifestFormat::Ron,
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct Convert {
+    /// Manifest to convert, or a project directory containing one.
+    #[clap(default_value = ".")]
+    pub input: PathBuf,
+
+    /// Write the converted manifest to a file instead of stdout.
+    #[clap(short, long)]
+    pub output: Option<PathBuf>,
+
+    /// Format to convert to, from the extension of the output by default.
+    #[clap(short, long, arg_enum)]
+    pub format: Option<Format>,
+}
+
+impl Convert {
+    pub fn run(self) -> Result<()> {
+        let input = match self.input.is_dir() {
+            true => Manifest::find(&self.input),
+            false => self.input,
+        };
+
+        // refuse to convert a manifest kiln can't load
+        Manifest::load(&input)?;
+
+        let format = match (self.format, &self.output) {
+            (Some(format), _) => format.into(),
+            (None, Some(output)) => ManifestFormat::of(output),
+            (None, None) => ManifestFormat::Toml,
+        };
+
+        // extends and includes are kept rather than merged, so the result loads the same
+        let source = format.write(&Manifest::load_table(&input)?)?;
+
+        match self.output {
+            Some(path) => fs::write(path, source)?,
+            None => print!("{}", source),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+
+use clap::Args;
+use kiln::{manifest::ManifestOverride, Project, Result};
+
+/// Project selection flags, shared by the commands loading a project.
+#[derive(Args)]
+pub struct ProjectArgs {
+    #[clap(default_value = ".")]
+    pub path: PathBuf,
+    /// Directory searched for `#include <...>` globals before those of the
+    /// manifest, can be repeated.
+    #[clap(short = 'I', long = "include-dir")]
+    pub include_dirs: Vec<PathBuf>,
+    /// Scene of the manifest to use, every shader without one. Tab cycles
+    /// through the scenes in `kiln show`.
+    #[clap(long)]
+    pub scene: Option<String>,
+    /// Override a manifest value, eg. `--set camera.fov=60`, applied after
+    /// `KILN_*` environment variables such as `KILN_CAMERA__FOV=60`.
+    #[clap(long = "set", value_name = "KEY=VALUE")]
+    pub overrides: Vec<ManifestOverride>,
+}
+
+impl ProjectArgs {
+    /// Overrides of the environment followed by those of `--set`.
+    pub fn overrides(&self) -> Result<Vec<ManifestOverride>> {
+        let mut overrides = ManifestOverride::from_env()?;
+        overrides.extend(self.overrides.iter().cloned());
+
+        Ok(overrides)
+    }
+
+    pub fn load(self) -> Result<Project> {
+        let overrides = self.overrides()?;
+
+        Project::builder(self.path)
+            .include_dirs(self.include_dirs)
+            .scene(self.scene)
+            .overrides(overrides)
+            .load()
+    }
+}
@@ -0,0 +1,73 @@
+use clap::{crate_authors, crate_version, ArgEnum, Parser};
+use kiln::{Result, Viewer};
+
+use crate::cli::{device::DeviceArgs, project::ProjectArgs};
+
+#[derive(Clone, Copy, Debug, ArgEnum)]
+pub enum Vsync {
+    Off,
+    On,
+    Mailbox,
+}
+
+impl Vsync {
+    pub fn present_mode(self) -> wgpu::PresentMode {
+        match self {
+            Vsync::Off => wgpu::PresentMode::Immediate,
+            Vsync::On => wgpu::PresentMode::Fifo,
+            Vsync::Mailbox => wgpu::PresentMode::Mailbox,
+        }
+    }
+}
+
+#[derive(Parser)]
+#[clap(version = crate_version!(), author = crate_authors!())]
+pub struct Show {
+    #[clap(flatten)]
+    pub project: ProjectArgs,
+    /// Prefer an extended range Rgba16Float surface when available.
+    #[clap(long)]
+    pub hdr: bool,
+    #[clap(long, arg_enum, default_value = "on")]
+    pub vsync: Vsync,
+    /// Limit the frame rate to this many frames per second.
+    #[clap(long, parse(try_from_str = parse_max_fps))]
+    pub max_fps: Option<f64>,
+    /// Stop redrawing while the window isn't focused.
+    #[clap(long)]
+    pub pause_when_unfocused: bool,
+    /// Print a rolling average of the time spent in each pass.
+    #[clap(long)]
+    pub profile: bool,
+    /// Resolution of screenshots taken with F12, as a multiple of the window size.
+    #[clap(long, default_value_t = 1)]
+    pub screenshot_scale: u32,
+    #[clap(flatten)]
+    pub device: DeviceArgs,
+}
+
+fn parse_max_fps(source: &str) -> std::result::Result<f64, String> {
+    match source.parse::<f64>() {
+        Ok(max_fps) if max_fps > 0.0 && max_fps.is_finite() => Ok(max_fps),
+        Ok(_) => Err(String::from("must be a positive number")),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+impl Show {
+    pub fn run(self) -> Result<()> {
+        let project = self.project.load()?;
+        let device = self.device.options(&project.manifest.device)?;
+
+        Viewer::builder(project)
+            .hdr(self.hdr)
+            .present_mode(self.vsync.present_mode())
+            .device(device)
+            .max_fps(self.max_fps)
+            .pause_when_unfocused(self.pause_when_unfocused)
+            .profile(self.profile)
+            .screenshot_scale(self.screenshot_scale)
+            .build()
+            .run()
+    }
+}
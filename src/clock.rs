@@ -0,0 +1,170 @@
+use std::time::Instant;
+
+use winit::event::VirtualKeyCode;
+
+/// Length of a single step, in seconds.
+const FRAME: f64 = 1.0 / 60.0;
+const SEEK: f64 = 1.0;
+const MIN_SPEED: f64 = 1.0 / 16.0;
+const MAX_SPEED: f64 = 16.0;
+
+/// Animation clock driving the `time` uniform, which can be paused, stepped,
+/// scrubbed and sped up or slowed down.
+#[derive(Clone, Debug)]
+pub struct Clock {
+    time: f64,
+    speed: f64,
+    paused: bool,
+    /// Time wraps back to zero after this many seconds.
+    pub period: Option<f64>,
+    /// Furthest point reached, used as the timeline length without a period.
+    duration: f64,
+    changed: bool,
+    last_tick: Instant,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self {
+            time: 0.0,
+            speed: 1.0,
+            paused: false,
+            period: None,
+            duration: 0.0,
+            changed: true,
+            last_tick: Instant::now(),
+        }
+    }
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    /// Whether time runs forward at normal speed, eg. for audio playback.
+    #[cfg(feature = "playback")]
+    pub fn realtime(&self) -> bool {
+        !self.paused && self.speed == 1.0
+    }
+
+    /// Advances the clock by the wall time since the last tick, returning
+    /// whether time moved since the previous tick.
+    pub fn tick(&mut self) -> bool {
+        let now = Instant::now();
+        let elapsed = (now - self.last_tick).as_secs_f64();
+        self.last_tick = now;
+
+        if !self.paused {
+            self.advance(elapsed * self.speed);
+        }
+
+        std::mem::take(&mut self.changed)
+    }
+
+    pub fn advance(&mut self, seconds: f64) {
+        self.seek(self.time + seconds);
+    }
+
+    pub fn seek(&mut self, time: f64) {
+        self.time = match self.period {
+            Some(period) if period > 0.0 => time.rem_euclid(period),
+            _ => time.max(0.0),
+        };
+        self.duration = self.duration.max(self.time);
+        self.changed = true;
+    }
+
+    /// Seeks to `position` between 0 and 1 along the timeline.
+    pub fn scrub(&mut self, position: f64) {
+        let length = match self.period {
+            Some(period) if period > 0.0 => period,
+            _ => self.duration,
+        };
+
+        self.seek(position.clamp(0.0, 1.0) * length);
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    /// Pauses and moves `frames` steps forward or back.
+    pub fn step(&mut self, frames: i32) {
+        self.paused = true;
+        self.advance(frames as f64 * FRAME);
+    }
+
+    pub fn rewind(&mut self) {
+        self.seek(0.0);
+    }
+
+    pub fn set_speed(&mut self, speed: f64) {
+        self.speed = speed.clamp(MIN_SPEED, MAX_SPEED);
+    }
+
+    /// Applies a playback hotkey, returning whether `key` was one.
+    pub fn key(&mut self, key: VirtualKeyCode) -> bool {
+        match key {
+            VirtualKeyCode::Space => self.toggle_pause(),
+            VirtualKeyCode::Period => self.step(1),
+            VirtualKeyCode::Comma => self.step(-1),
+            VirtualKeyCode::Right => self.advance(SEEK),
+            VirtualKeyCode::Left => self.advance(-SEEK),
+            VirtualKeyCode::Home => self.rewind(),
+            VirtualKeyCode::Up => self.set_speed(self.speed * 2.0),
+            VirtualKeyCode::Down => self.set_speed(self.speed / 2.0),
+            VirtualKeyCode::Key0 => self.set_speed(1.0),
+            _ => return false,
+        }
+
+        true
+    }
+
+    /// Short description of the clock state, eg. `7.30s paused 0.5x`.
+    pub fn status(&self) -> String {
+        let mut status = format!("{:.2}s", self.time);
+
+        if self.paused {
+            status += " paused";
+        }
+
+        if self.speed != 1.0 {
+            status += &format!(" {}x", self.speed);
+        }
+
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_seek_and_wrap() {
+        let mut clock = Clock::new();
+        clock.step(3);
+        assert!(clock.paused);
+        assert!((clock.time() - 3.0 * FRAME).abs() < 1e-9);
+
+        clock.advance(-10.0);
+        assert_eq!(clock.time(), 0.0);
+
+        clock.period = Some(10.0);
+        clock.seek(12.5);
+        assert!((clock.time() - 2.5).abs() < 1e-9);
+        clock.advance(-3.0);
+        assert!((clock.time() - 9.5).abs() < 1e-9);
+
+        clock.scrub(0.5);
+        assert!((clock.time() - 5.0).abs() < 1e-9);
+
+        clock.set_speed(100.0);
+        assert_eq!(clock.speed, MAX_SPEED);
+    }
+}
@@ -0,0 +1,276 @@
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use linked_hash_map::LinkedHashMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+use crate::{
+    error::Result,
+    manifest::{Manifest, ManifestDependency, ManifestLibrary},
+};
+
+/// The part of a library's manifest that matters to projects depending on it.
+#[derive(Debug, Default, Deserialize)]
+struct LibraryManifest {
+    #[serde(default)]
+    library: ManifestLibrary,
+}
+
+/// A shader library resolved to a directory on disk.
+#[derive(Clone, Debug)]
+pub struct Dependency {
+    pub name: String,
+    /// Directory of the library, relative to the project unless given as absolute.
+    pub path: PathBuf,
+    pub git: Option<String>,
+    pub rev: Option<String>,
+    /// Exported files by global include name, eg. `noise/fbm`.
+    pub exports: LinkedHashMap<String, PathBuf>,
+    /// Sha256 of the library's manifest and shaders.
+    pub hash: String,
+}
+
+impl Dependency {
+    pub fn resolve(root: &Path, name: &str, manifest: &ManifestDependency) -> Result<Self> {
+        let path = match (&manifest.path, &manifest.git) {
+            (Some(path), _) => path.clone(),
+            (None, Some(_)) => Path::new("vendor").join(name),
+            (None, None) => return Err(DependencyError::NoSource(name.to_string()).into()),
+        };
+
+        let dir = root.join(&path);
+        if !dir.is_dir() {
+            return Err(DependencyError::NotFound(name.to_string(), dir).into());
+        }
+
+        let manifest_path = Manifest::find(&dir);
+        let library = if manifest_path.is_file() {
+            toml::Value::Table(Manifest::load_table(&manifest_path)?)
+                .try_into::<LibraryManifest>()
+                .map_err(|err| DependencyError::Library(name.to_string(), err))?
+        } else {
+            LibraryManifest::default()
+        };
+
+        let shaders = wgsl_files(&dir)?;
+
+        let exports = if library.library.exports.is_empty() {
+            shaders
+                .iter()
+                .map(|file| {
+                    let module = file.with_extension("");
+                    let module = module.to_string_lossy().replace('\\', "/");
+                    (format!("{}/{}", name, module), dir.join(file))
+                })
+                .collect()
+        } else {
+            library
+                .library
+                .exports
+                .iter()
+                .map(|(module, file)| (format!("{}/{}", name, module), dir.join(file)))
+                .collect()
+        };
+
+        let mut hasher = Sha256::new();
+        let manifest_file = manifest_path.strip_prefix(&dir).unwrap_or(&manifest_path);
+        for file in std::iter::once(manifest_file).chain(shaders.iter().map(PathBuf::as_path)) {
+            let contents = match fs::read(dir.join(file)) {
+                Ok(contents) => contents,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            hasher.update(file.to_string_lossy().replace('\\', "/").as_bytes());
+            hasher.update([0]);
+            hasher.update(&contents);
+            hasher.update([0]);
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            path,
+            git: manifest.git.clone(),
+            rev: manifest.rev.clone(),
+            exports,
+            hash: format!("{:x}", hasher.finalize()),
+        })
+    }
+
+    /// Resolves every dependency of `manifest`, a project in `root`.
+    pub fn resolve_all(root: &Path, manifest: &Manifest) -> Result<Vec<Self>> {
+        manifest
+            .dependencies
+            .iter()
+            .map(|(name, dependency)| Self::resolve(root, name, dependency))
+            .collect()
+    }
+
+    /// Exported files of `dependencies` by global include name.
+    pub fn exports(dependencies: &[Self]) -> HashMap<String, PathBuf> {
+        dependencies
+            .iter()
+            .flat_map(|dependency| dependency.exports.clone())
+            .collect()
+    }
+}
+
+/// `.wgsl` files below `dir`, relative to it and sorted.
+fn wgsl_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let pattern = format!(
+        "{}/**/*.wgsl",
+        glob::Pattern::escape(&dir.to_string_lossy())
+    );
+
+    let mut files = Vec::new();
+    for path in glob::glob(&pattern).expect("valid glob pattern") {
+        let path = path.map_err(io::Error::from)?;
+        files.push(path.strip_prefix(dir).unwrap_or(&path).to_path_buf());
+    }
+    files.sort();
+
+    Ok(files)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct LockedDependency {
+    pub name: String,
+    pub path: PathBuf,
+    pub git: Option<String>,
+    pub rev: Option<String>,
+    pub hash: String,
+}
+
+impl From<&Dependency> for LockedDependency {
+    fn from(dependency: &Dependency) -> Self {
+        Self {
+            name: dependency.name.clone(),
+            path: dependency.path.clone(),
+            git: dependency.git.clone(),
+            rev: dependency.rev.clone(),
+            hash: dependency.hash.clone(),
+        }
+    }
+}
+
+/// `Kiln.lock`, pinning the path and contents of each dependency.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Lockfile {
+    #[serde(default, rename = "dependency")]
+    pub dependencies: Vec<LockedDependency>,
+}
+
+impl Lockfile {
+    pub const DEFAULT_PATH: &'static str = "Kiln.lock";
+
+    pub fn new(dependencies: &[Dependency]) -> Self {
+        Self {
+            dependencies: dependencies.iter().map(LockedDependency::from).collect(),
+        }
+    }
+
+    pub fn load(root: &Path) -> Result<Option<Self>> {
+        match fs::read_to_string(root.join(Self::DEFAULT_PATH)) {
+            Ok(source) => Ok(Some(
+                toml::from_str(&source).map_err(DependencyError::Lockfile)?,
+            )),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, root: &Path) -> Result<()> {
+        let source = toml::to_string(self).map_err(DependencyError::Serialize)?;
+        fs::write(root.join(Self::DEFAULT_PATH), source)?;
+
+        Ok(())
+    }
+
+    /// Checks `dependencies` against the lockfile of the project in `root`,
+    /// without writing it.
+    ///
+    /// A locked dependency whose path or contents changed is an error until the
+    /// lockfile is updated with `kiln lock`. Dependencies that aren't locked yet
+    /// are accepted as they are.
+    pub fn verify(root: &Path, dependencies: &[Dependency]) -> Result<()> {
+        let lockfile = Self::load(root)?;
+        let locked = lockfile
+            .as_ref()
+            .map_or(&[][..], |lockfile| &lockfile.dependencies);
+
+        for dependency in dependencies {
+            let entry = locked.iter().find(|entry| entry.name == dependency.name);
+
+            if let Some(entry) = entry {
+                if *entry != LockedDependency::from(dependency) {
+                    return Err(DependencyError::Changed(dependency.name.clone()).into());
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DependencyError {
+    #[error("dependency '{0}' needs a `path` or `git`")]
+    NoSource(String),
+    #[error("dependency '{0}' not found at '{}'", .1.display())]
+    NotFound(String, PathBuf),
+    #[error("invalid manifest for dependency '{0}': {1}")]
+    Library(String, toml::de::Error),
+    #[error("dependency '{0}' changed since it was locked, run `kiln lock` to update Kiln.lock")]
+    Changed(String),
+    #[error("invalid lockfile: {0}")]
+    Lockfile(toml::de::Error),
+    #[error("failed to write lockfile: {0}")]
+    Serialize(toml::ser::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exports_and_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("lib/noise")).unwrap();
+        fs::write(root.join("lib/noise/fbm.wgsl"), "fbm;").unwrap();
+
+        let manifest = ManifestDependency {
+            path: Some(PathBuf::from("lib")),
+            git: None,
+            rev: None,
+        };
+        let dependency = Dependency::resolve(root, "shared", &manifest).unwrap();
+        assert_eq!(
+            dependency.exports.keys().collect::<Vec<_>>(),
+            ["shared/noise/fbm"]
+        );
+
+        // unlocked dependencies are accepted, but only `kiln lock` writes the lockfile
+        Lockfile::verify(root, std::slice::from_ref(&dependency)).unwrap();
+        assert_eq!(Lockfile::load(root).unwrap(), None);
+
+        let lockfile = Lockfile::new(std::slice::from_ref(&dependency));
+        lockfile.save(root).unwrap();
+        assert_eq!(Lockfile::load(root).unwrap(), Some(lockfile));
+        Lockfile::verify(root, &[dependency]).unwrap();
+
+        fs::write(root.join("lib/noise/fbm.wgsl"), "changed;").unwrap();
+        let dependency = Dependency::resolve(root, "shared", &manifest).unwrap();
+
+        assert!(matches!(
+            Lockfile::verify(root, &[dependency]),
+            Err(crate::error::Error::Dependency(DependencyError::Changed(_)))
+        ));
+    }
+}
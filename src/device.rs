@@ -0,0 +1,240 @@
+use futures_lite::future;
+use thiserror::Error;
+
+use crate::manifest::{ManifestAdapter, ManifestDevice, ManifestLimits};
+
+const BACKENDS: &[(&str, wgpu::Backends)] = &[
+    ("vulkan", wgpu::Backends::VULKAN),
+    ("metal", wgpu::Backends::METAL),
+    ("dx12", wgpu::Backends::DX12),
+    ("dx11", wgpu::Backends::DX11),
+    ("gl", wgpu::Backends::GL),
+    ("primary", wgpu::Backends::PRIMARY),
+    ("secondary", wgpu::Backends::SECONDARY),
+    ("all", wgpu::Backends::all()),
+];
+
+/// Parses a comma separated list of backends, eg. `vulkan,gl`.
+pub fn parse_backends(source: &str) -> Result<wgpu::Backends, DeviceError> {
+    let mut backends = wgpu::Backends::empty();
+
+    for name in source.split(',').map(str::trim) {
+        let backend = BACKENDS
+            .iter()
+            .find(|(backend, _)| backend.eq_ignore_ascii_case(name))
+            .ok_or_else(|| DeviceError::UnknownBackend(name.to_string()))?;
+
+        backends |= backend.1;
+    }
+
+    Ok(backends)
+}
+
+/// Every named feature flag and its kebab-case name, eg. `shader-float64`.
+fn features() -> impl Iterator<Item = (String, wgpu::Features)> {
+    (0..u64::BITS).filter_map(|bit| {
+        let feature = wgpu::Features::from_bits(1 << bit)?;
+        let name = format!("{:?}", feature).to_lowercase().replace('_', "-");
+
+        Some((name, feature))
+    })
+}
+
+pub fn parse_feature(name: &str) -> Result<wgpu::Features, DeviceError> {
+    let name = name.to_lowercase().replace('_', "-");
+
+    features()
+        .find(|(feature, _)| *feature == name)
+        .map(|(_, feature)| feature)
+        .ok_or(DeviceError::UnknownFeature(name))
+}
+
+pub fn feature_names(set: wgpu::Features) -> Vec<String> {
+    features()
+        .filter(|(_, feature)| set.contains(*feature))
+        .map(|(name, _)| name)
+        .collect()
+}
+
+#[derive(Clone, Debug)]
+pub struct DeviceOptions {
+    pub backends: wgpu::Backends,
+    pub adapter: Option<ManifestAdapter>,
+    pub force_fallback_adapter: bool,
+    /// Features the device fails to be created without.
+    pub required_features: wgpu::Features,
+    /// Features requested when the adapter supports them.
+    pub optional_features: wgpu::Features,
+    pub limits: ManifestLimits,
+}
+
+impl Default for DeviceOptions {
+    fn default() -> Self {
+        Self {
+            backends: wgpu::Backends::all(),
+            adapter: None,
+            force_fallback_adapter: false,
+            required_features: wgpu::Features::empty(),
+            optional_features: wgpu::Features::empty(),
+            limits: ManifestLimits::default(),
+        }
+    }
+}
+
+impl DeviceOptions {
+    pub fn from_manifest(manifest: &ManifestDevice) -> Result<Self, DeviceError> {
+        let mut options = Self {
+            adapter: manifest.adapter.clone(),
+            force_fallback_adapter: manifest.fallback,
+            limits: manifest.limits,
+            ..Default::default()
+        };
+
+        if let Some(ref backend) = manifest.backend {
+            options.backends = parse_backends(backend)?;
+        }
+
+        for feature in manifest.features.iter() {
+            options.required_features |= parse_feature(feature)?;
+        }
+
+        Ok(options)
+    }
+
+    fn request_adapter(
+        &self,
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface>,
+    ) -> Result<wgpu::Adapter, DeviceError> {
+        let compatible = |adapter: &wgpu::Adapter| {
+            compatible_surface.is_none_or(|surface| adapter.is_surface_supported(surface))
+        };
+        // adapters chosen by index or name are only fallback adapters if they run on the cpu
+        let fallback = |adapter: &wgpu::Adapter| {
+            !self.force_fallback_adapter || adapter.get_info().device_type == wgpu::DeviceType::Cpu
+        };
+
+        match self.adapter {
+            Some(ManifestAdapter::Index(index)) => {
+                let adapter = instance
+                    .enumerate_adapters(self.backends)
+                    .nth(index)
+                    .ok_or_else(|| DeviceError::AdapterNotFound(index.to_string()))?;
+
+                if !compatible(&adapter) {
+                    return Err(DeviceError::IncompatibleAdapter(adapter.get_info().name));
+                }
+
+                if !fallback(&adapter) {
+                    return Err(DeviceError::NotFallback(adapter.get_info().name));
+                }
+
+                Ok(adapter)
+            }
+            Some(ManifestAdapter::Name(ref name)) => instance
+                .enumerate_adapters(self.backends)
+                .filter(compatible)
+                .filter(fallback)
+                .find(|adapter| {
+                    let info = adapter.get_info();
+                    info.name.to_lowercase().contains(&name.to_lowercase())
+                })
+                .ok_or_else(|| DeviceError::AdapterNotFound(name.clone())),
+            None => {
+                let adapter_fut = instance.request_adapter(&wgpu::RequestAdapterOptions {
+                    power_preference: wgpu::PowerPreference::HighPerformance,
+                    force_fallback_adapter: self.force_fallback_adapter,
+                    compatible_surface,
+                });
+
+                future::block_on(adapter_fut).ok_or(DeviceError::NoAdapter)
+            }
+        }
+    }
+
+    pub fn request_device(
+        &self,
+        instance: &wgpu::Instance,
+        compatible_surface: Option<&wgpu::Surface>,
+    ) -> Result<(wgpu::Adapter, wgpu::Device, wgpu::Queue), DeviceError> {
+        let adapter = self.request_adapter(instance, compatible_surface)?;
+
+        let missing = self.required_features - adapter.features();
+        if !missing.is_empty() {
+            return Err(DeviceError::MissingFeatures {
+                adapter: adapter.get_info().name,
+                features: feature_names(missing).join(", "),
+            });
+        }
+
+        let features = self.required_features | (self.optional_features & adapter.features());
+
+        let mut limits = match self.limits {
+            ManifestLimits::Default => wgpu::Limits::default(),
+            ManifestLimits::Downlevel => wgpu::Limits::downlevel_defaults(),
+            ManifestLimits::DownlevelWebgl2 => wgpu::Limits::downlevel_webgl2_defaults(),
+            ManifestLimits::Adapter => adapter.limits(),
+        };
+
+        if features.contains(wgpu::Features::PUSH_CONSTANTS) {
+            limits.max_push_constant_size = adapter.limits().max_push_constant_size;
+        }
+
+        let device_fut = adapter.request_device(
+            &wgpu::DeviceDescriptor {
+                label: Some("kiln-device"),
+                features,
+                limits,
+            },
+            None,
+        );
+
+        let (device, queue) = future::block_on(device_fut)?;
+
+        Ok((adapter, device, queue))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum DeviceError {
+    #[error("unknown backend '{0}', expected one of vulkan, metal, dx12, dx11, gl, primary, secondary or all")]
+    UnknownBackend(String),
+    #[error("unknown feature '{0}'")]
+    UnknownFeature(String),
+    #[error("no suitable adapter found")]
+    NoAdapter,
+    #[error("no adapter matching '{0}', see `kiln info` for available adapters")]
+    AdapterNotFound(String),
+    #[error("adapter '{0}' isn't a fallback adapter")]
+    NotFallback(String),
+    #[error("adapter '{0}' can't present to the window surface")]
+    IncompatibleAdapter(String),
+    #[error("adapter '{adapter}' doesn't support required features: {features}")]
+    MissingFeatures { adapter: String, features: String },
+    #[error("failed to request device: {0}")]
+    RequestDevice(#[from] wgpu::RequestDeviceError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn feature_names_round_trip() {
+        let features = wgpu::Features::SHADER_FLOAT64 | wgpu::Features::PUSH_CONSTANTS;
+        let names = feature_names(features);
+
+        assert!(names.contains(&String::from("shader-float64")));
+        assert!(names.contains(&String::from("push-constants")));
+
+        for name in names {
+            assert!(features.contains(parse_feature(&name).unwrap()));
+        }
+
+        assert_eq!(
+            parse_feature("SHADER_FLOAT64").unwrap(),
+            wgpu::Features::SHADER_FLOAT64
+        );
+        assert!(parse_feature("float128").is_err());
+    }
+}
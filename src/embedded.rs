@@ -0,0 +1,168 @@
+use glam::Mat4;
+
+use crate::{
+    error::Result, manifest::ManifestCamera, project::Project, shader::ShaderUniforms,
+    upscale::Upscaler,
+};
+
+/// Camera a frame is seen through, in place of the manifest's.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Camera {
+    /// Transform from camera to world space.
+    pub view: Mat4,
+    /// Projection from camera to clip space. Kiln's `camera_ray` mirrors x, so
+    /// meshes only line up with raymarched shaders if the projection does too,
+    /// as [`ManifestCamera::projection`] does.
+    pub projection: Mat4,
+}
+
+impl Camera {
+    pub fn from_manifest(camera: &ManifestCamera, aspect: f32) -> Self {
+        Self {
+            view: camera.view(),
+            projection: camera.projection(aspect),
+        }
+    }
+}
+
+/// Size, time and camera of a frame drawn by [`Embedded::render`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    /// Seconds passed to the shaders.
+    pub time: f32,
+    /// Camera of the frame, the manifest's if `None`.
+    pub camera: Option<Camera>,
+    /// Whether to dispatch compute shaders, advancing simulations.
+    pub simulate: bool,
+}
+
+impl Frame {
+    pub fn new(width: u32, height: u32, time: f32) -> Self {
+        Self {
+            width,
+            height,
+            time,
+            camera: None,
+            simulate: true,
+        }
+    }
+
+    pub fn camera(mut self, camera: Camera) -> Self {
+        self.camera = Some(camera);
+        self
+    }
+
+    pub fn simulate(mut self, simulate: bool) -> Self {
+        self.simulate = simulate;
+        self
+    }
+}
+
+/// Renders a project with a device, queue and target owned by the caller, eg.
+/// into an editor viewport or as a post processing pass.
+///
+/// ```no_run
+/// # fn frame(device: &wgpu::Device, queue: &wgpu::Queue, view: &wgpu::TextureView) -> kiln::Result<()> {
+/// let project = kiln::Project::builder("examples/forest").load()?;
+/// let format = wgpu::TextureFormat::Rgba8UnormSrgb;
+/// let mut embedded = kiln::Embedded::new(project, device, queue, format)?;
+///
+/// let mut encoder = device.create_command_encoder(&Default::default());
+/// embedded.render(device, queue, &mut encoder, view, kiln::Frame::new(1280, 720, 2.5))?;
+/// queue.submit(std::iter::once(encoder.finish()));
+/// # Ok(())
+/// # }
+/// ```
+pub struct Embedded {
+    pub project: Project,
+    /// Format of the textures rendered into.
+    pub format: wgpu::TextureFormat,
+    upscaler: Option<Upscaler>,
+}
+
+impl Embedded {
+    /// Builds the shaders of `project` to render into `format` textures.
+    pub fn new(
+        mut project: Project,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+    ) -> Result<Self> {
+        project.update(device, queue, format)?;
+
+        Ok(Self {
+            project,
+            format,
+            upscaler: None,
+        })
+    }
+
+    /// Reloads the manifest if it changed, see [`Project::reload`]. Shaders
+    /// are rebuilt by the next [`Embedded::render`].
+    pub fn reload(&mut self) -> Result<bool> {
+        self.project.reload()
+    }
+
+    /// Records drawing `frame` into `target_view`, a `format` texture of the
+    /// frame's size. Nothing is drawn while a shader fails to build.
+    pub fn render(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target_view: &wgpu::TextureView,
+        frame: Frame,
+    ) -> Result<()> {
+        let project = &mut self.project;
+        project.update(device, queue, self.format)?;
+
+        if project.invalid {
+            return Ok(());
+        }
+
+        let manifest_project = &project.manifest.project;
+        let (width, height) = manifest_project.internal_size(frame.width, frame.height);
+
+        // render scale, resolution and supersampling render offscreen and upscale into the target
+        if (width, height) == (frame.width, frame.height) {
+            self.upscaler = None;
+        } else {
+            let filter = manifest_project.upscale;
+            let matches = self
+                .upscaler
+                .as_ref()
+                .is_some_and(|upscaler| upscaler.matches(width, height, self.format, filter));
+
+            if !matches {
+                self.upscaler = Some(Upscaler::new(device, width, height, self.format, filter));
+            }
+        }
+
+        let project_view = self
+            .upscaler
+            .as_ref()
+            .map_or(target_view, |upscaler| &upscaler.view);
+
+        let uniforms = match frame.camera {
+            Some(camera) => {
+                ShaderUniforms::new(width, height, frame.time, camera.view, camera.projection)
+            }
+            None => project.uniforms(width, height, frame.time),
+        };
+
+        project.resize(device, width, height);
+        project.write_uniforms(queue, &uniforms);
+        if frame.simulate {
+            project.dispatch(encoder, None);
+        }
+        project.render(encoder, project_view, None);
+
+        if let Some(ref upscaler) = self.upscaler {
+            upscaler.blit(encoder, target_view);
+        }
+
+        Ok(())
+    }
+}
@@ -2,16 +2,36 @@ use std::{io, path::PathBuf};
 
 use thiserror::Error;
 
-use crate::shader_processor::ShaderProcessorError;
+use crate::{
+    audio::AudioError, dependency::DependencyError, device::DeviceError, manifest::ManifestError,
+    manifest::ManifestWriteError, mesh::MeshError, shader::ShaderError,
+    shader_processor::ShaderProcessorError, storage::StorageBufferError,
+};
 
 #[derive(Debug, Error)]
 pub enum Error {
+    #[error("{0} shader entry point(s) failed to check")]
+    Check(usize),
     #[error("path doesn't exist: {0}")]
     InvalidPath(PathBuf),
     #[error("error loading manifest: {0}")]
-    Manifest(#[from] toml::de::Error),
+    Manifest(#[from] ManifestError),
+    #[error("error writing manifest: {0}")]
+    ManifestWrite(#[from] ManifestWriteError),
+    #[error("shader error: {0}")]
+    Shader(#[from] ShaderError),
     #[error("shader processor error: {0}")]
     ShaderProcessor(#[from] ShaderProcessorError),
+    #[error("storage buffer error: {0}")]
+    StorageBuffer(#[from] StorageBufferError),
+    #[error("audio error: {0}")]
+    Audio(#[from] AudioError),
+    #[error("dependency error: {0}")]
+    Dependency(#[from] DependencyError),
+    #[error("mesh error: {0}")]
+    Mesh(#[from] MeshError),
+    #[error("device error: {0}")]
+    Device(#[from] DeviceError),
     #[error("surface error: {0}")]
     Surface(#[from] wgpu::SurfaceError),
     #[error("io error: {0}")]
@@ -20,6 +40,14 @@ pub enum Error {
     Os(#[from] winit::error::OsError),
     #[error("wgpu error: {0}")]
     Wgpu(#[from] wgpu::Error),
+    #[error("buffer map error: {0}")]
+    BufferAsync(#[from] wgpu::BufferAsyncError),
+    #[error("unsupported texture format: {0:?}")]
+    UnsupportedFormat(wgpu::TextureFormat),
+    #[error("unsupported msaa sample count: {0}, expected 1 or 4")]
+    UnsupportedSampleCount(u32),
+    #[error("image error: {0}")]
+    Image(#[from] image::ImageError),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
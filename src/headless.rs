@@ -0,0 +1,119 @@
+use crate::{
+    capture::{self, Capture},
+    clock::Clock,
+    device::DeviceOptions,
+    error::Result,
+    manifest::ManifestFilter,
+    project::Project,
+    upscale::Upscaler,
+};
+
+/// Builds a [`Headless`] renderer, see [`Headless::builder`].
+pub struct HeadlessBuilder {
+    project: Project,
+    device: Option<DeviceOptions>,
+    format: wgpu::TextureFormat,
+}
+
+impl HeadlessBuilder {
+    /// Adapter and device to request, from the manifest's `[device]` table by default.
+    pub fn device(mut self, device: DeviceOptions) -> Self {
+        self.device = Some(device);
+        self
+    }
+
+    /// Format of the rendered frames, `Rgba8UnormSrgb` by default.
+    pub fn format(mut self, format: wgpu::TextureFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Requests the device and builds the project's shaders.
+    pub fn build(self) -> Result<Headless> {
+        let options = match self.device {
+            Some(device) => device,
+            None => DeviceOptions::from_manifest(&self.project.manifest.device)?,
+        };
+        let instance = wgpu::Instance::new(options.backends);
+        let (_, device, queue) = options.request_device(&instance, None)?;
+
+        let mut project = self.project;
+        project.update(&device, &queue, self.format)?;
+
+        Ok(Headless {
+            project,
+            device,
+            queue,
+            format: self.format,
+        })
+    }
+}
+
+/// Renders frames of a project without a window, as `kiln export` does.
+pub struct Headless {
+    pub project: Project,
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub format: wgpu::TextureFormat,
+}
+
+impl Headless {
+    /// Starts building a headless renderer for `project`, eg.
+    ///
+    /// ```no_run
+    /// let project = kiln::Project::builder("examples/forest").load()?;
+    /// let mut headless = kiln::Headless::builder(project).build()?;
+    /// headless.render(1920, 1080, 2.5)?.save("forest.png".as_ref())?;
+    /// # Ok::<(), kiln::Error>(())
+    /// ```
+    pub fn builder(project: Project) -> HeadlessBuilder {
+        HeadlessBuilder {
+            project,
+            device: None,
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+        }
+    }
+
+    /// Renders the frame at `time` seconds and reads it back from the gpu.
+    pub fn render(&mut self, width: u32, height: u32, time: f64) -> Result<Capture> {
+        let (device, queue, format) = (&self.device, &self.queue, self.format);
+        let project = &mut self.project;
+        project.update(device, queue, format)?;
+
+        let target = capture::create_target(device, width, height, format);
+        let target_view = target.create_view(&Default::default());
+
+        let mut encoder = device.create_command_encoder(&Default::default());
+        let mut clock = Clock::new();
+        clock.period = project.manifest.project.r#loop;
+        clock.seek(time);
+
+        let supersample = project.manifest.project.supersample.max(1);
+        let (internal_width, internal_height) = (width * supersample, height * supersample);
+
+        // supersampled renders go through an upscaler to be filtered down to the output size
+        let upscaler = (supersample > 1).then(|| {
+            Upscaler::new(
+                device,
+                internal_width,
+                internal_height,
+                format,
+                ManifestFilter::Bilinear,
+            )
+        });
+        let project_view = upscaler
+            .as_ref()
+            .map_or(&target_view, |upscaler| &upscaler.view);
+
+        let uniforms = project.uniforms(internal_width, internal_height, clock.time() as f32);
+        project.resize(device, internal_width, internal_height);
+        project.draw(queue, &mut encoder, project_view, &uniforms, None);
+
+        if let Some(ref upscaler) = upscaler {
+            upscaler.blit(&mut encoder, &target_view);
+        }
+        queue.submit(std::iter::once(encoder.finish()));
+
+        Capture::read(device, queue, &target, width, height, format)
+    }
+}
@@ -0,0 +1,24 @@
+// x: spectrum in 0..1, y: waveform in -1..1, 512 texels wide
+@group(2) @binding(0)
+var audio_texture: texture_1d<f32>;
+
+fn audio_texel(x: f32) -> vec2<f32> {
+	let width = textureDimensions(audio_texture);
+	let position = clamp(x, 0.0, 1.0) * f32(width - 1);
+	let index = i32(position);
+
+	let a = textureLoad(audio_texture, index, 0).xy;
+	let b = textureLoad(audio_texture, min(index + 1, width - 1), 0).xy;
+
+	return mix(a, b, fract(position));
+}
+
+// Loudness of the frequency at x, from 0 (lowest) to 1 (nyquist).
+fn audio_spectrum(x: f32) -> f32 {
+	return audio_texel(x).x;
+}
+
+// Waveform of the last 512 samples, x from 0 (oldest) to 1 (newest).
+fn audio_waveform(x: f32) -> f32 {
+	return audio_texel(x).y;
+}
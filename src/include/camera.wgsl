@@ -4,7 +4,8 @@
 
 fn camera_ray(input: Input) -> Ray {
 	let org = uniforms.view.w;
-	let dir = uniforms.view * vec4<f32>(-input.coord.x * uniforms.aspect, input.coord.y, -1.0, 0.0);
+	let scale = 1.0 / uniforms.projection[1][1];
+	let dir = uniforms.view * vec4<f32>(-input.coord.x * uniforms.aspect * scale, input.coord.y * scale, -1.0, 0.0);
 
 	return Ray(org.xyz, normalize(dir.xyz));
 }
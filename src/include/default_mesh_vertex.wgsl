@@ -0,0 +1,6 @@
+#include <kiln/mesh>
+
+@vertex
+fn vert(vertex: Vertex) -> MeshOutput {
+	return mesh_vertex(vertex);
+}
@@ -1,10 +1,4 @@
-struct Uniforms {
-	view: mat4x4<f32>,
-	aspect: f32,
-};
-
-@group(0) @binding(0)
-var<uniform> uniforms: Uniforms;
+#include <kiln/uniforms>
 
 struct VertexOutput {
 	@builtin(position) position: vec4<f32>,
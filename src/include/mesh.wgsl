@@ -0,0 +1,36 @@
+#include <kiln/uniforms>
+
+// Vertex buffer layout of meshes.
+struct Vertex {
+	@location(0) position: vec3<f32>,
+	@location(1) normal: vec3<f32>,
+	@location(2) uv: vec2<f32>,
+}
+
+// Compatible with `Input`, adding the world space position and normal.
+struct MeshOutput {
+	@builtin(position) position: vec4<f32>,
+	@location(0) uv: vec2<f32>,
+	@location(1) coord: vec2<f32>,
+	@location(2) world_position: vec3<f32>,
+	@location(3) normal: vec3<f32>,
+}
+
+// Transforms a vertex by `model`, eg. one matrix per instance.
+fn mesh_vertex_with(vertex: Vertex, model: mat4x4<f32>) -> MeshOutput {
+	let world = model * vec4<f32>(vertex.position, 1.0);
+
+	var out: MeshOutput;
+
+	out.position = uniforms.view_projection * world;
+	out.uv = vertex.uv;
+	out.coord = out.position.xy / out.position.w;
+	out.world_position = world.xyz;
+	out.normal = normalize((model * vec4<f32>(vertex.normal, 0.0)).xyz);
+
+	return out;
+}
+
+fn mesh_vertex(vertex: Vertex) -> MeshOutput {
+	return mesh_vertex_with(vertex, uniforms.model);
+}
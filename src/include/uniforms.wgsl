@@ -1,7 +1,14 @@
 struct Uniforms {
+	// camera to world
 	view: mat4x4<f32>,
 	aspect: f32,
 	time: f32,
+	resolution: vec2<f32>,
+	// object to world, set by the shader's transform
+	model: mat4x4<f32>,
+	projection: mat4x4<f32>,
+	// world to clip space
+	view_projection: mat4x4<f32>,
 }
 
 @group(0) @binding(0)
@@ -0,0 +1,45 @@
+//! Kiln renders wgsl shader projects described by a `Kiln.toml` manifest.
+//!
+//! The [`ShaderProcessor`] resolves `#include`s and can be used on its own, eg.
+//! in an asset pipeline. A [`Project`] loads a manifest and builds its
+//! [`Shader`]s, which are drawn either by a [`Headless`] renderer, in a
+//! window by a [`Viewer`] or with a device and target of your own by
+//! [`Embedded`].
+
+#![deny(unsafe_op_in_unsafe_fn)]
+
+pub mod audio;
+pub mod capture;
+pub mod clock;
+pub mod dependency;
+pub mod device;
+pub mod embedded;
+pub mod error;
+pub mod headless;
+pub mod manifest;
+pub mod mesh;
+mod overlay;
+#[cfg(feature = "playback")]
+pub mod playback;
+pub mod profiler;
+pub mod project;
+pub mod render;
+pub mod shader;
+pub mod shader_processor;
+pub mod storage;
+pub mod upscale;
+pub mod viewer;
+pub mod window;
+
+pub use embedded::{Camera, Embedded, Frame};
+pub use error::{Error, Result};
+pub use headless::{Headless, HeadlessBuilder};
+pub use manifest::Manifest;
+pub use project::{Project, ProjectBuilder};
+pub use shader::Shader;
+pub use shader_processor::ShaderProcessor;
+pub use viewer::{Viewer, ViewerBuilder};
+
+// versions of the types in the public api
+pub use glam;
+pub use wgpu;
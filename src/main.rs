@@ -1,21 +1,35 @@
-#![deny(unsafe_op_in_unsafe_fn)]
-
-mod error;
-mod manifest;
-mod render;
-mod shader;
-mod shader_processor;
-mod show;
-mod window;
+mod cli {
+    pub mod check;
+    pub mod device;
+    pub mod export;
+    pub mod info;
+    pub mod lock;
+    pub mod manifest_command;
+    pub mod project;
+    pub mod show;
+}
 
 use clap::{crate_authors, crate_version, Parser, Subcommand};
-use show::Show;
+use cli::{
+    check::Check, export::Export, info::Info, lock::Lock, manifest_command::ManifestCommand,
+    show::Show,
+};
 
 #[derive(Subcommand)]
 enum Command {
     Init,
+    /// Check the shaders of a kiln project and list their entry points.
+    Check(Check),
     /// Show a kiln shader.
     Show(Show),
+    /// Render a single frame of a kiln project to an image.
+    Export(Export),
+    /// List the available adapters, their features and limits.
+    Info(Info),
+    /// Resolve the dependencies of a kiln project and pin them in Kiln.lock.
+    Lock(Lock),
+    /// Inspect the project manifest format.
+    Manifest(ManifestCommand),
 }
 
 #[derive(Parser)]
@@ -28,10 +42,18 @@ struct Args {
 fn main() {
     let args = Args::parse();
 
-    match args.command {
-        Command::Init => {}
-        Command::Show(show) => {
-            show.run().unwrap();
-        }
+    let result = match args.command {
+        Command::Init => Ok(()),
+        Command::Check(check) => check.run(),
+        Command::Show(show) => show.run(),
+        Command::Export(export) => export.run(),
+        Command::Info(info) => info.run(),
+        Command::Lock(lock) => lock.run(),
+        Command::Manifest(manifest) => manifest.run(),
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
     }
 }
@@ -1,51 +1,385 @@
 use std::{
-    fs,
+    collections::BTreeMap,
+    fs, io,
     path::{Path, PathBuf},
+    str::FromStr,
 };
 
-use glam::{Mat4, Vec3};
+use glam::{EulerRot, Mat4, Quat, Vec3};
 use linked_hash_map::LinkedHashMap;
-use serde::{Deserialize, Serialize};
+use schemars::{
+    schema::{RootSchema, Schema},
+    JsonSchema,
+};
+use serde::{de, Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::error::{Error, Result};
 
-use crate::error::Result;
+type Table = toml::value::Table;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(rename = "kebab-case")]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct Manifest {
+    /// Manifest this one is layered over. Resolved while loading, so always
+    /// empty in a loaded manifest.
+    pub extends: Option<PathBuf>,
+    /// Glob patterns of manifests merged in before this one. Resolved while
+    /// loading, so always empty in a loaded manifest.
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Every file the manifest was loaded from, for hot reloading.
+    #[serde(skip)]
+    #[schemars(skip)]
+    pub sources: Vec<PathBuf>,
     pub project: ManifestProject,
     #[serde(default)]
     pub camera: ManifestCamera,
     #[serde(default)]
+    pub device: ManifestDevice,
+    pub audio: Option<ManifestAudio>,
+    /// Shader libraries whose exports are included as `<name/module>`.
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, ManifestDependency>")]
+    pub dependencies: LinkedHashMap<String, ManifestDependency>,
+    /// Exports of this project when used as a dependency.
+    pub library: Option<ManifestLibrary>,
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, ManifestBuffer>")]
+    pub buffers: LinkedHashMap<String, ManifestBuffer>,
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, ManifestShader>")]
     pub shaders: LinkedHashMap<String, ManifestShader>,
+    /// Named variations of the project, selected with `--scene`.
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, ManifestScene>")]
+    pub scenes: LinkedHashMap<String, ManifestScene>,
 }
 
 impl Manifest {
     pub const DEFAULT_PATH: &'static str = "Kiln.toml";
+    /// Manifest file names looked for in a project, in order of preference.
+    pub const PATHS: [&'static str; 3] = [Self::DEFAULT_PATH, "Kiln.json", "Kiln.ron"];
+    /// Fields also accepted under a snake case alias, by schema definition.
+    const ALIASES: [(&'static str, &'static str, &'static str); 5] = [
+        ("ManifestProject", "clear-color", "clear_color"),
+        ("ManifestProject", "render-scale", "render_scale"),
+        ("ManifestProject", "include-dirs", "include_dirs"),
+        ("ManifestShader", "vertex-entry", "vertex_entry"),
+        ("ManifestScene", "clear-color", "clear_color"),
+    ];
+
+    /// Path of the manifest of the project in `dir`, `Kiln.toml` if there is none.
+    pub fn find(dir: &Path) -> PathBuf {
+        Self::PATHS
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+            .unwrap_or_else(|| dir.join(Self::DEFAULT_PATH))
+    }
 
+    /// Loads the manifest at `path` along with any manifests it extends or includes.
+    ///
+    /// Each manifest is parsed as toml, json or ron depending on its extension.
+    /// Layers are merged in order: the extended manifest, the includes sorted by
+    /// path, then `path` itself. Tables, including shader and buffer entries, are
+    /// merged key by key and keep the position of their first definition; any
+    /// other value replaces the inherited one. Relative paths are resolved
+    /// against the manifest they are written in.
     pub fn load(path: &Path) -> Result<Self> {
+        Self::load_with_overrides(path, &[])
+    }
+
+    /// Loads the manifest at `path`, then applies `overrides` in order.
+    pub fn load_with_overrides(path: &Path, overrides: &[ManifestOverride]) -> Result<Self> {
+        let mut layers = Vec::new();
+        let mut value = Self::load_layer(path, &mut Vec::new(), &mut layers)?;
+
+        let mut manifest = match layers.as_slice() {
+            // parse a lone manifest from source, so errors know their position
+            [(path, source)] if ManifestFormat::of(path).is_toml() => {
+                Self::parse(path, source).map_err(ManifestError::from)?
+            }
+            _ => toml::Value::Table(value.clone())
+                .try_into::<Self>()
+                .map_err(|err| ManifestError::from(ManifestParseError::layered(&layers, err)))?,
+        };
+
+        // overrides are checked one at a time, so errors name the one at fault
+        for manifest_override in overrides {
+            manifest_override.apply(&mut value)?;
+            manifest = toml::Value::Table(value.clone())
+                .try_into::<Self>()
+                .map_err(|err| ManifestError::Override(manifest_override.origin.clone(), err))?;
+        }
+
+        manifest.sources = layers.into_iter().map(|(path, _)| path).collect();
+        Ok(manifest)
+    }
+
+    pub fn parse(path: &Path, source: &str) -> std::result::Result<Self, ManifestParseError> {
+        toml::from_str(source).map_err(|err| ManifestParseError::new(path, source, err))
+    }
+
+    /// Reads the manifest at `path` as a table, without resolving what it extends or includes.
+    pub fn load_table(path: &Path) -> Result<Table> {
         let source = fs::read_to_string(path)?;
-        Ok(toml::from_str(&source)?)
+        let table = ManifestFormat::of(path)
+            .parse(path, &source)
+            .map_err(ManifestError::from)?;
+
+        Ok(table)
+    }
+
+    fn load_layer(
+        path: &Path,
+        stack: &mut Vec<PathBuf>,
+        layers: &mut Vec<(PathBuf, String)>,
+    ) -> Result<Table> {
+        let canonical = path
+            .canonicalize()
+            .map_err(|_| Error::InvalidPath(path.to_path_buf()))?;
+        if stack.contains(&canonical) {
+            return Err(ManifestError::Cycle(path.to_path_buf()).into());
+        }
+
+        let source = fs::read_to_string(path)?;
+        let mut table = ManifestFormat::of(path)
+            .parse(path, &source)
+            .map_err(ManifestError::from)?;
+        let dir = canonical.parent().unwrap_or(Path::new("/")).to_path_buf();
+
+        // paths in inherited manifests are relative to them rather than the project
+        if let Some(root) = stack.first() {
+            if root.parent() != Some(dir.as_path()) {
+                rebase(&mut table, &dir);
+            }
+        }
+
+        let extends = table.remove("extends");
+        let include = table.remove("include");
+        layers.push((path.to_path_buf(), source));
+        stack.push(canonical);
+
+        let mut merged = match extends {
+            Some(toml::Value::String(extends)) => {
+                Self::load_layer(&path_in(path, &extends), stack, layers)?
+            }
+            Some(_) => return Err(ManifestError::InvalidKey("extends").into()),
+            None => Table::new(),
+        };
+
+        let patterns = match include {
+            Some(toml::Value::Array(patterns)) => patterns,
+            Some(_) => return Err(ManifestError::InvalidKey("include").into()),
+            None => Vec::new(),
+        };
+
+        for pattern in patterns {
+            let pattern = match pattern {
+                toml::Value::String(pattern) => pattern,
+                _ => return Err(ManifestError::InvalidKey("include").into()),
+            };
+            let full = path_in(path, &pattern);
+            let full = full.to_string_lossy();
+
+            let mut paths = glob::glob(&full)
+                .map_err(|err| ManifestError::Pattern(pattern.clone(), err))?
+                .collect::<std::result::Result<Vec<_>, _>>()
+                .map_err(io::Error::from)?;
+            paths.sort();
+
+            for include in paths {
+                let layer = Self::load_layer(&include, stack, layers)?;
+                merge(&mut merged, layer);
+            }
+        }
+
+        stack.pop();
+        merge(&mut merged, table);
+
+        Ok(merged)
+    }
+
+    /// The manifest with scene `name` applied, keeping only its shaders, merging
+    /// its camera over the project's and replacing the clear color it sets.
+    pub fn scene(&self, name: &str) -> std::result::Result<Self, ManifestError> {
+        let scene = self
+            .scenes
+            .get(name)
+            .ok_or_else(|| ManifestError::UnknownScene(name.to_string()))?;
+        let mut manifest = self.clone();
+
+        if let Some(ref shaders) = scene.shaders {
+            if let Some(shader) = shaders
+                .iter()
+                .find(|shader| !self.shaders.contains_key(*shader))
+            {
+                return Err(ManifestError::UnknownShader(
+                    name.to_string(),
+                    shader.clone(),
+                ));
+            }
+
+            manifest.shaders = self
+                .shaders
+                .iter()
+                .filter(|(shader, _)| shaders.contains(shader))
+                .map(|(shader, manifest)| (shader.clone(), manifest.clone()))
+                .collect();
+        }
+
+        if let Some(ref camera) = scene.camera {
+            let mut table = match toml::Value::try_from(&self.camera) {
+                Ok(toml::Value::Table(table)) => table,
+                _ => Table::new(),
+            };
+            merge(&mut table, camera.clone());
+
+            manifest.camera = toml::Value::Table(table)
+                .try_into()
+                .map_err(|err| ManifestError::SceneCamera(name.to_string(), err))?;
+        }
+
+        if let Some(clear_color) = scene.clear_color {
+            manifest.project.clear_color = clear_color;
+        }
+
+        Ok(manifest)
+    }
+
+    /// JSON schema of the manifest, for editor completion and validation.
+    pub fn schema() -> RootSchema {
+        let mut schema = schemars::schema_for!(Manifest);
+
+        // schemars doesn't know about serde aliases
+        for (definition, field, alias) in Self::ALIASES {
+            if let Some(Schema::Object(object)) = schema.definitions.get_mut(definition) {
+                let properties = &mut object.object().properties;
+                if let Some(property) = properties.get(field).cloned() {
+                    properties.insert(alias.to_string(), property);
+                }
+            }
+        }
+
+        schema
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(rename = "kebab-case")]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct ManifestProject {
     pub name: String,
     pub author: Option<String>,
+    #[serde(default = "default_clear_color", alias = "clear_color")]
+    pub clear_color: [f64; 4],
+    #[serde(alias = "render_scale")]
+    pub render_scale: Option<f32>,
+    pub resolution: Option<[u32; 2]>,
+    #[serde(default)]
+    pub upscale: ManifestFilter,
+    /// Samples per pixel of the multisampled colour and depth targets.
+    #[serde(default = "default_samples")]
+    pub msaa: u32,
+    /// Renders at this multiple of the output size and filters down, for
+    /// fullscreen shaders msaa doesn't smooth.
+    #[serde(default = "default_samples")]
+    pub supersample: u32,
+    /// Wraps time back to zero after this many seconds.
+    pub r#loop: Option<f64>,
+    /// Directories searched for `#include <...>` globals, relative to the project.
+    #[serde(default, alias = "include_dirs")]
+    pub include_dirs: Vec<PathBuf>,
+}
+
+const fn default_samples() -> u32 {
+    1
+}
+
+const fn default_clear_color() -> [f64; 4] {
+    [0.0, 0.0, 0.0, 1.0]
+}
+
+impl ManifestProject {
+    pub fn clear_color(&self) -> wgpu::Color {
+        let [r, g, b, a] = self.clear_color;
+        wgpu::Color { r, g, b, a }
+    }
+
+    /// Size shaders are rendered at for a window of `width` by `height`,
+    /// including supersampling.
+    pub fn internal_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let supersample = self.supersample.max(1);
+
+        if let Some([width, height]) = self.resolution {
+            return (width.max(1) * supersample, height.max(1) * supersample);
+        }
+
+        let scale = self.render_scale.unwrap_or(1.0) * supersample as f32;
+        let scaled = |size: u32| ((size as f32 * scale).round() as u32).max(1);
+
+        (scaled(width), scaled(height))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestFilter {
+    Nearest,
+    #[default]
+    Bilinear,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ManifestAdapter {
+    Index(usize),
+    Name(String),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum ManifestLimits {
+    #[default]
+    Default,
+    Downlevel,
+    DownlevelWebgl2,
+    Adapter,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ManifestDevice {
+    pub backend: Option<String>,
+    pub adapter: Option<ManifestAdapter>,
+    #[serde(default)]
+    pub fallback: bool,
+    #[serde(default)]
+    pub features: Vec<String>,
+    #[serde(default)]
+    pub limits: ManifestLimits,
 }
 
 const fn default_direction() -> Vec3 {
     Vec3::Z
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(rename = "kebab-case")]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct ManifestCamera {
     #[serde(default)]
+    #[schemars(with = "[f32; 3]")]
     pub position: Vec3,
     #[serde(default = "default_direction")]
+    #[schemars(with = "[f32; 3]")]
     pub direction: Vec3,
+    /// Vertical field of view in degrees.
+    #[serde(default = "default_fov")]
+    pub fov: f32,
+}
+
+const fn default_fov() -> f32 {
+    90.0
 }
 
 impl Default for ManifestCamera {
@@ -53,20 +387,1140 @@ impl Default for ManifestCamera {
         Self {
             position: Default::default(),
             direction: default_direction(),
+            fov: default_fov(),
         }
     }
 }
 
 impl ManifestCamera {
+    pub const NEAR: f32 = 0.01;
+    pub const FAR: f32 = 1000.0;
+
     pub fn view(&self) -> Mat4 {
         let d = self.direction.normalize_or_zero();
         Mat4::from_translation(self.position) * Mat4::look_at_rh(Vec3::ZERO, d, Vec3::Y).inverse()
     }
+
+    pub fn projection(&self, aspect: f32) -> Mat4 {
+        let fov = self.fov.clamp(1.0, 179.0).to_radians();
+
+        // `camera_ray` mirrors x, so meshes are mirrored too in order to line up with it
+        Mat4::from_scale(Vec3::new(-1.0, 1.0, 1.0))
+            * Mat4::perspective_rh(fov, aspect, Self::NEAR, Self::FAR)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestShape {
+    Sphere,
+    Cube,
+    Plane,
+    Torus,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ManifestMesh {
+    Shape(ManifestShape),
+    File(PathBuf),
+}
+
+const fn default_vertices() -> u32 {
+    6
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-#[serde(rename = "kebab-case")]
+/// Number of instances drawn, either fixed or one per element of a storage buffer.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum ManifestInstances {
+    Count(u32),
+    Buffer(String),
+}
+
+impl Default for ManifestInstances {
+    fn default() -> Self {
+        Self::Count(1)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ManifestTransform {
+    #[serde(default)]
+    #[schemars(with = "[f32; 3]")]
+    pub translation: Vec3,
+    /// Euler angles in degrees, applied in x, y, z order.
+    #[serde(default)]
+    #[schemars(with = "[f32; 3]")]
+    pub rotation: Vec3,
+    #[serde(default = "default_scale")]
+    #[schemars(with = "[f32; 3]")]
+    pub scale: Vec3,
+}
+
+const fn default_scale() -> Vec3 {
+    Vec3::ONE
+}
+
+impl Default for ManifestTransform {
+    fn default() -> Self {
+        Self {
+            translation: Vec3::ZERO,
+            rotation: Vec3::ZERO,
+            scale: default_scale(),
+        }
+    }
+}
+
+impl ManifestTransform {
+    pub fn matrix(&self) -> Mat4 {
+        let [x, y, z] = self.rotation.to_array().map(f32::to_radians);
+        let rotation = Quat::from_euler(EulerRot::XYZ, x, y, z);
+
+        Mat4::from_scale_rotation_translation(self.scale, rotation, self.translation)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ManifestAudio {
+    /// Wav or ogg file, relative to the project.
+    pub file: PathBuf,
+    #[serde(default = "default_volume")]
+    pub volume: f32,
+}
+
+const fn default_volume() -> f32 {
+    1.0
+}
+
+/// A named variation of the project.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ManifestScene {
+    /// Shaders drawn in the scene, in manifest order, every shader if unset.
+    pub shaders: Option<Vec<String>>,
+    /// Camera fields set by the scene, merged over the project's camera.
+    #[schemars(with = "Option<ManifestCamera>")]
+    pub camera: Option<Table>,
+    #[serde(alias = "clear_color")]
+    pub clear_color: Option<[f64; 4]>,
+}
+
+/// A shader library in a local directory or a vendored git checkout.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ManifestDependency {
+    /// Directory of the library, `vendor/<name>` by default for git dependencies.
+    pub path: Option<PathBuf>,
+    /// Repository the checkout was cloned from, recorded in the lockfile.
+    pub git: Option<String>,
+    pub rev: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ManifestLibrary {
+    /// Module names and their files, every `.wgsl` file of the library if empty.
+    #[serde(default)]
+    #[schemars(with = "BTreeMap<String, PathBuf>")]
+    pub exports: LinkedHashMap<String, PathBuf>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub struct ManifestBuffer {
+    pub size: Option<u64>,
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+    pub count: Option<u64>,
+    pub init: Option<PathBuf>,
+}
+
+const fn default_workgroups() -> [u32; 3] {
+    [1, 1, 1]
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestBlend {
+    #[default]
+    Replace,
+    Alpha,
+    Additive,
+    Multiply,
+}
+
+impl ManifestBlend {
+    pub fn state(&self) -> Option<wgpu::BlendState> {
+        let component = |src_factor, dst_factor| wgpu::BlendComponent {
+            src_factor,
+            dst_factor,
+            operation: wgpu::BlendOperation::Add,
+        };
+
+        match self {
+            ManifestBlend::Replace => None,
+            ManifestBlend::Alpha => Some(wgpu::BlendState::ALPHA_BLENDING),
+            ManifestBlend::Additive => Some(wgpu::BlendState {
+                color: component(wgpu::BlendFactor::One, wgpu::BlendFactor::One),
+                alpha: component(wgpu::BlendFactor::One, wgpu::BlendFactor::One),
+            }),
+            ManifestBlend::Multiply => Some(wgpu::BlendState {
+                color: component(wgpu::BlendFactor::Dst, wgpu::BlendFactor::Zero),
+                alpha: component(wgpu::BlendFactor::DstAlpha, wgpu::BlendFactor::Zero),
+            }),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestLoad {
+    Clear,
+    Load,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
 pub struct ManifestShader {
     pub fragment: Option<PathBuf>,
     pub vertex: Option<PathBuf>,
+    pub compute: Option<PathBuf>,
+    pub entry: Option<String>,
+    #[serde(alias = "vertex_entry")]
+    pub vertex_entry: Option<String>,
+    #[serde(default = "default_workgroups")]
+    pub workgroups: [u32; 3],
+    #[serde(default)]
+    pub blend: ManifestBlend,
+    pub load: Option<ManifestLoad>,
+    /// Obj, gltf or glb file, or a built-in shape, drawn instead of a fullscreen quad.
+    pub mesh: Option<ManifestMesh>,
+    /// Resolution of built-in shapes.
+    pub subdivisions: Option<u32>,
+    /// Vertices drawn per instance without a mesh.
+    #[serde(default = "default_vertices")]
+    pub vertices: u32,
+    #[serde(default)]
+    pub instances: ManifestInstances,
+    #[serde(default)]
+    pub transform: ManifestTransform,
+}
+
+/// File format of a manifest, from its extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ManifestFormat {
+    Toml,
+    Json,
+    Ron,
+}
+
+impl ManifestFormat {
+    /// Format of the manifest at `path`, toml unless it ends in `.json` or `.ron`.
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::Json,
+            Some("ron") => Self::Ron,
+            _ => Self::Toml,
+        }
+    }
+
+    pub fn is_toml(self) -> bool {
+        self == Self::Toml
+    }
+
+    pub fn parse(
+        self,
+        path: &Path,
+        source: &str,
+    ) -> std::result::Result<Table, ManifestParseError> {
+        match self {
+            Self::Toml => {
+                toml::from_str(source).map_err(|err| ManifestParseError::new(path, source, err))
+            }
+            Self::Json => serde_json::from_str::<NullableTable>(source)
+                .map(|table| table.0)
+                .map_err(|err| {
+                    let position = (err.line() > 0).then_some((err.line(), err.column()));
+                    let message = err.to_string();
+                    let message = match message.rsplit_once(" at line ") {
+                        Some((message, _)) if position.is_some() => message.to_string(),
+                        _ => message,
+                    };
+                    ManifestParseError::at(path, position, &message)
+                }),
+            Self::Ron => ron::from_str::<NullableTable>(source)
+                .map(|table| table.0)
+                .map_err(|err| {
+                    let position = Some((err.position.line, err.position.col));
+                    ManifestParseError::at(path, position, &err.code)
+                }),
+        }
+    }
+
+    pub fn write(self, table: &Table) -> std::result::Result<String, ManifestWriteError> {
+        let invalid = |err: &dyn std::fmt::Display| ManifestWriteError(err.to_string());
+
+        match self {
+            // as a value, which puts the plain values of a table before its subtables
+            Self::Toml => {
+                toml::to_string(&toml::Value::Table(table.clone())).map_err(|err| invalid(&err))
+            }
+            Self::Json => serde_json::to_string_pretty(table)
+                .map(|source| source + "\n")
+                .map_err(|err| invalid(&err)),
+            Self::Ron => ron::ser::to_string_pretty(table, Default::default())
+                .map(|source| source + "\n")
+                .map_err(|err| invalid(&err)),
+        }
+    }
+}
+
+/// A json or ron manifest read as a toml table in source order, with null
+/// members dropped since toml has no null. Ron structs like `(name: "demo")`
+/// read as tables too.
+struct NullableTable(Table);
+
+impl<'de> Deserialize<'de> for NullableTable {
+    fn deserialize<D: de::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        match deserializer.deserialize_any(NullableVisitor)? {
+            Some(toml::Value::Table(table)) => Ok(Self(table)),
+            _ => Err(de::Error::custom("expected the manifest to be a table")),
+        }
+    }
+}
+
+/// Visits a value as toml, none for a null.
+struct NullableVisitor;
+
+impl<'de> de::Visitor<'de> for NullableVisitor {
+    type Value = Option<toml::Value>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a manifest value")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> std::result::Result<Self::Value, E> {
+        Ok(Some(toml::Value::Boolean(value)))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> std::result::Result<Self::Value, E> {
+        Ok(Some(toml::Value::Integer(value)))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> std::result::Result<Self::Value, E> {
+        i64::try_from(value)
+            .map(|value| Some(toml::Value::Integer(value)))
+            .map_err(|_| E::custom(format!("{} is too large", value)))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> std::result::Result<Self::Value, E> {
+        Ok(Some(toml::Value::Float(value)))
+    }
+
+    fn visit_char<E: de::Error>(self, value: char) -> std::result::Result<Self::Value, E> {
+        Ok(Some(toml::Value::String(value.to_string())))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> std::result::Result<Self::Value, E> {
+        Ok(Some(toml::Value::String(value.to_string())))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_none<E: de::Error>(self) -> std::result::Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let mut array = Vec::new();
+        while let Some(value) = seq.next_element_seed(NullableVisitor)? {
+            // dropping it would shift the rest of the array
+            array.push(value.ok_or_else(|| de::Error::custom("arrays can't contain null"))?);
+        }
+
+        Ok(Some(toml::Value::Array(array)))
+    }
+
+    fn visit_map<A: de::MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> std::result::Result<Self::Value, A::Error> {
+        let mut table = Table::new();
+        // keys through `deserialize_any` too, as ron reads struct fields as identifiers
+        while let Some((key, value)) = map.next_entry_seed(NullableVisitor, NullableVisitor)? {
+            let key = match key {
+                Some(toml::Value::String(key)) => key,
+                _ => return Err(de::Error::custom("keys must be strings")),
+            };
+            if let Some(value) = value {
+                table.insert(key, value);
+            }
+        }
+
+        Ok(Some(toml::Value::Table(table)))
+    }
+}
+
+impl<'de> de::DeserializeSeed<'de> for NullableVisitor {
+    type Value = Option<toml::Value>;
+
+    fn deserialize<D: de::Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> std::result::Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+/// A manifest value replaced from the command line or environment, eg.
+/// `camera.position=[0, 500, 0]`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ManifestOverride {
+    pub key: Vec<String>,
+    pub value: toml::Value,
+    /// Environment variable or `key=value` argument the override came from.
+    pub origin: String,
+}
+
+impl ManifestOverride {
+    pub const ENV_PREFIX: &'static str = "KILN_";
+
+    /// Parses `value` as a toml value, or a plain string if it isn't one.
+    fn new(
+        origin: &str,
+        key: Vec<String>,
+        value: &str,
+    ) -> std::result::Result<Self, ManifestError> {
+        if key.iter().any(String::is_empty) {
+            return Err(ManifestError::InvalidOverride(origin.to_string()));
+        }
+
+        let value = toml::from_str::<Table>(&format!("value = {}", value))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .unwrap_or_else(|| toml::Value::String(value.to_string()));
+
+        Ok(Self {
+            key,
+            value,
+            origin: origin.to_string(),
+        })
+    }
+
+    /// Overrides from `KILN_*` environment variables, with `__` separating
+    /// keys, eg. `KILN_CAMERA__FOV=60`.
+    pub fn from_env() -> std::result::Result<Vec<Self>, ManifestError> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// Overrides from the `KILN_*` variables among `vars`, sorted by key.
+    /// Variables not naming a top level key of the manifest, such as
+    /// `KILN_LOG`, are left alone.
+    pub fn from_vars(
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> std::result::Result<Vec<Self>, ManifestError> {
+        let keys = Self::keys();
+        let mut overrides = vars
+            .into_iter()
+            .filter_map(|(name, value)| {
+                let key = name.strip_prefix(Self::ENV_PREFIX)?;
+                let key = key.split("__").map(str::to_lowercase).collect::<Vec<_>>();
+                if !keys.contains(&key[0]) {
+                    return None;
+                }
+
+                Some(Self::new(&name, key, &value))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        overrides.sort_by(|a, b| a.key.cmp(&b.key));
+
+        Ok(overrides)
+    }
+
+    /// Top level keys of the manifest that can be overridden, every one but
+    /// `extends` and `include` which are resolved before overrides apply.
+    fn keys() -> Vec<String> {
+        Manifest::schema()
+            .schema
+            .object
+            .map(|object| object.properties.into_keys().collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|key| !matches!(key.as_str(), "extends" | "include"))
+            .collect()
+    }
+
+    fn apply(&self, table: &mut Table) -> std::result::Result<(), ManifestError> {
+        let invalid = || ManifestError::InvalidOverride(self.origin.clone());
+        let (last, parents) = self.key.split_last().ok_or_else(invalid)?;
+
+        // environment variables can't contain dashes, so `clear_color` also sets `clear-color`
+        let key_in = |table: &Table, key: &str| match table.contains_key(key) {
+            true => key.to_string(),
+            false => key.replace('_', "-"),
+        };
+
+        let mut table = table;
+        for parent in parents {
+            let parent = key_in(table, parent);
+            table = table
+                .entry(parent)
+                .or_insert_with(|| toml::Value::Table(Table::new()))
+                .as_table_mut()
+                .ok_or_else(invalid)?;
+        }
+
+        table.insert(key_in(table, last), self.value.clone());
+
+        Ok(())
+    }
+}
+
+impl FromStr for ManifestOverride {
+    type Err = ManifestError;
+
+    fn from_str(source: &str) -> std::result::Result<Self, Self::Err> {
+        let (key, value) = source
+            .split_once('=')
+            .ok_or_else(|| ManifestError::InvalidOverride(source.to_string()))?;
+
+        Self::new(
+            source,
+            key.trim().split('.').map(String::from).collect(),
+            value.trim(),
+        )
+    }
+}
+
+/// `name` relative to the directory of the manifest at `path`.
+fn path_in(path: &Path, name: &str) -> PathBuf {
+    path.parent().unwrap_or(Path::new("")).join(name)
+}
+
+/// Merges `layer` over `base`, tables key by key and any other value replacing
+/// the one in `base`.
+fn merge(base: &mut Table, layer: Table) {
+    for (key, value) in layer {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(layer)) => merge(base, layer),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+/// Tables of the entries in `table[key]`, eg. the shaders.
+fn entries<'a>(table: &'a mut Table, key: &str) -> Vec<&'a mut Table> {
+    match table.get_mut(key) {
+        Some(toml::Value::Table(entries)) => entries
+            .iter_mut()
+            .filter_map(|(_, entry)| entry.as_table_mut())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Makes the relative paths of a manifest in `dir` absolute.
+fn rebase(table: &mut Table, dir: &Path) {
+    let rebase_value = |value: &mut toml::Value| {
+        if let toml::Value::String(path) = value {
+            if Path::new(path).is_relative() {
+                *path = dir.join(&path).to_string_lossy().into_owned();
+            }
+        }
+    };
+
+    for shader in entries(table, "shaders") {
+        for key in ["fragment", "vertex", "compute"] {
+            shader.get_mut(key).map(rebase_value);
+        }
+
+        if let Some(mesh) = shader.get_mut("mesh") {
+            if mesh.clone().try_into::<ManifestShape>().is_err() {
+                rebase_value(mesh);
+            }
+        }
+    }
+
+    for buffer in entries(table, "buffers") {
+        buffer.get_mut("init").map(rebase_value);
+    }
+
+    for dependency in entries(table, "dependencies") {
+        dependency.get_mut("path").map(rebase_value);
+    }
+
+    if let Some(toml::Value::Table(audio)) = table.get_mut("audio") {
+        audio.get_mut("file").map(rebase_value);
+    }
+
+    if let Some(toml::Value::Table(project)) = table.get_mut("project") {
+        for key in ["include-dirs", "include_dirs"] {
+            if let Some(toml::Value::Array(include_dirs)) = project.get_mut(key) {
+                include_dirs.iter_mut().for_each(rebase_value);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ManifestError {
+    #[error("{0}")]
+    Parse(#[from] ManifestParseError),
+    #[error("'{}' extends or includes itself", .0.display())]
+    Cycle(PathBuf),
+    #[error("invalid include pattern '{0}': {1}")]
+    Pattern(String, glob::PatternError),
+    #[error("`{0}` has the wrong type")]
+    InvalidKey(&'static str),
+    #[error("invalid override '{0}', expected `key.path=value`")]
+    InvalidOverride(String),
+    #[error("invalid override '{0}': {1}")]
+    Override(String, toml::de::Error),
+    #[error("no scene named '{0}'")]
+    UnknownScene(String),
+    #[error("scene '{0}' draws unknown shader '{1}'")]
+    UnknownShader(String, String),
+    #[error("invalid camera in scene '{0}': {1}")]
+    SceneCamera(String, toml::de::Error),
+}
+
+/// A manifest that couldn't be written in another format.
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct ManifestWriteError(String);
+
+#[derive(Debug, Error)]
+#[error("{}{}: {message}{}", .path.display(), location(*.position), hint(.suggestion))]
+pub struct ManifestParseError {
+    pub path: PathBuf,
+    /// One based line and column of the error.
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+    pub suggestion: Option<String>,
+}
+
+impl ManifestParseError {
+    fn new(path: &Path, source: &str, err: toml::de::Error) -> Self {
+        let position = err.line_col();
+
+        // the position is reported separately
+        let message = err.to_string();
+        let message = match message.rsplit_once(" at line ") {
+            Some((message, _)) if position.is_some() => message.to_string(),
+            _ => message,
+        };
+
+        Self {
+            path: path.to_path_buf(),
+            position: locate(source, &message)
+                .or(position)
+                .map(|(line, column)| (line + 1, column + 1)),
+            suggestion: suggest(&message),
+            message,
+        }
+    }
+
+    /// Error at one based `position` in a manifest that isn't toml.
+    fn at(path: &Path, position: Option<(usize, usize)>, err: &dyn std::fmt::Display) -> Self {
+        let message = err.to_string();
+
+        Self {
+            path: path.to_path_buf(),
+            position,
+            suggestion: suggest(&message),
+            message,
+        }
+    }
+
+    /// Error in a manifest merged from `layers`, attributed to the layer
+    /// containing the offending key if it can be found.
+    fn layered(layers: &[(PathBuf, String)], err: toml::de::Error) -> Self {
+        let message = err.to_string();
+        let (path, position) = layers
+            .iter()
+            .rev()
+            .find_map(|(path, source)| Some((path, locate(source, &message)?)))
+            .map_or((&layers[0].0, None), |(path, position)| {
+                (path, Some(position))
+            });
+
+        Self {
+            path: path.clone(),
+            position: position.map(|(line, column)| (line + 1, column + 1)),
+            suggestion: suggest(&message),
+            message,
+        }
+    }
+}
+
+/// An unknown field or variant, parsed from the text of serde's error as
+/// reported by toml, eg. "unknown field `nmae`, expected one of `name`,
+/// `author` for key `project`". The `unknown_messages` test pins this format.
+#[derive(Debug, PartialEq)]
+struct Unknown<'a> {
+    name: &'a str,
+    expected: Vec<&'a str>,
+    /// Table containing a field, or key holding a variant. None for top level
+    /// fields.
+    table: Option<&'a str>,
+    field: bool,
+}
+
+impl<'a> Unknown<'a> {
+    fn parse(message: &'a str) -> Option<Self> {
+        let (message, table) = match message.split_once(" for key `") {
+            Some((message, table)) => (message, Some(table.split_once('`')?.0)),
+            None => (message, None),
+        };
+        let (rest, field) = match message.strip_prefix("unknown field `") {
+            Some(rest) => (rest, true),
+            None => (message.strip_prefix("unknown variant `")?, false),
+        };
+        let (name, expected) = rest.split_once('`')?;
+
+        Some(Self {
+            name,
+            expected: expected.split('`').skip(1).step_by(2).collect(),
+            table,
+            field,
+        })
+    }
+}
+
+/// Zero based position of the key in an unknown field or variant error, the
+/// last segment of the key path for variants.
+///
+/// toml reports these at or past their table rather than at the key itself, so
+/// the key is looked up between the table's header and the next one.
+fn locate(source: &str, message: &str) -> Option<(usize, usize)> {
+    let unknown = Unknown::parse(message)?;
+    let (table, key) = match (unknown.field, unknown.table) {
+        (true, table) => (table, unknown.name),
+        (false, Some(path)) => match path.rsplit_once('.') {
+            Some((table, key)) => (Some(table), key),
+            None => (None, path),
+        },
+        (false, None) => return None,
+    };
+    let is_header = |text: &str| text.trim_start().starts_with('[');
+
+    let start = match table {
+        Some(table) => {
+            let header = format!("[{}]", table);
+            source.lines().position(|text| text.trim() == header)? + 1
+        }
+        None => 0,
+    };
+
+    source
+        .lines()
+        .enumerate()
+        .skip(start)
+        .take_while(|(_, text)| !is_header(text))
+        .find_map(|(line, text)| {
+            let column = text.find(key)?;
+            let rest = text[column + key.len()..].trim_start();
+            (text[..column].trim().is_empty() && rest.starts_with(['=', '.']))
+                .then_some((line, column))
+        })
+}
+
+fn location(position: Option<(usize, usize)>) -> String {
+    match position {
+        Some((line, column)) => format!(":{}:{}", line, column),
+        None => String::new(),
+    }
+}
+
+fn hint(suggestion: &Option<String>) -> String {
+    match suggestion {
+        Some(suggestion) => format!(", did you mean `{}`?", suggestion),
+        None => String::new(),
+    }
+}
+
+/// Closest expected name to the one in serde's unknown field and variant errors.
+fn suggest(message: &str) -> Option<String> {
+    let unknown = Unknown::parse(message)?;
+
+    unknown
+        .expected
+        .iter()
+        .map(|candidate| {
+            (
+                strsim::damerau_levenshtein(unknown.name, candidate),
+                candidate,
+            )
+        })
+        .filter(|&(distance, _)| distance <= (unknown.name.len() / 3).max(1))
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        let path = Path::new("Kiln.toml");
+
+        let manifest = Manifest::parse(
+            path,
+            "[project]\nname = \"test\"\nclear-color = [1.0, 0.0, 0.0, 1.0]\nrender_scale = 0.5\n",
+        )
+        .unwrap();
+        assert_eq!(manifest.project.clear_color, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(manifest.project.render_scale, Some(0.5));
+
+        let err = Manifest::parse(
+            path,
+            "[camera]\nfov = 60.0\n\n[project]\nname = \"test\"\nclear-colour = [0, 0, 0, 1]\n",
+        )
+        .unwrap_err();
+        assert_eq!(err.suggestion.as_deref(), Some("clear-color"));
+        assert_eq!(err.position, Some((6, 1)));
+
+        let err = Manifest::parse(
+            path,
+            "[project]\nname = \"test\"\n\n[shaders.main]\nfragment = \"main.wgsl\"\nblend = \"aditive\"\n",
+        )
+        .unwrap_err();
+        assert_eq!(err.suggestion.as_deref(), Some("additive"));
+        assert_eq!(err.position, Some((6, 1)));
+
+        let err = Manifest::parse(path, "[project]\nname = \"test\"\n  upscale = \"nearst\"\n")
+            .unwrap_err();
+        assert_eq!(err.suggestion.as_deref(), Some("nearest"));
+        assert_eq!(err.position, Some((3, 3)));
+    }
+
+    /// Whether `value` matches `schema`, checking the types and keys of values
+    /// and following `$ref`s into the definitions of `root`.
+    fn matches(
+        root: &serde_json::Value,
+        schema: &serde_json::Value,
+        value: &serde_json::Value,
+    ) -> bool {
+        use serde_json::Value;
+
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.trim_start_matches("#/definitions/");
+            return matches(root, &root["definitions"][name], value);
+        }
+
+        let any = |key: &str| {
+            schema[key]
+                .as_array()
+                .is_none_or(|options| options.iter().any(|option| matches(root, option, value)))
+        };
+        if !any("anyOf") || !any("oneOf") {
+            return false;
+        }
+
+        let types = match &schema["type"] {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let name = match value {
+            Value::Null => "null",
+            Value::Bool(_) => "boolean",
+            Value::Number(number) if number.is_f64() => "number",
+            Value::Number(_) => "integer",
+            Value::String(_) => "string",
+            Value::Array(_) => "array",
+            Value::Object(_) => "object",
+        };
+        let typed = types.is_empty()
+            || types.contains(&name)
+            || (name == "integer" && types.contains(&"number"));
+        if !typed {
+            return false;
+        }
+
+        match value {
+            Value::Object(object) => object.iter().all(|(key, value)| {
+                match (
+                    schema["properties"].get(key),
+                    &schema["additionalProperties"],
+                ) {
+                    (Some(property), _) => matches(root, property, value),
+                    (None, Value::Bool(allowed)) => *allowed,
+                    (None, Value::Null) => true,
+                    (None, additional) => matches(root, additional, value),
+                }
+            }),
+            Value::Array(array) => match &schema["items"] {
+                items @ Value::Object(_) => array.iter().all(|item| matches(root, items, item)),
+                _ => true,
+            },
+            _ => true,
+        }
+    }
+
+    #[test]
+    fn schema_accepts_aliases() {
+        let schema = serde_json::to_value(Manifest::schema()).unwrap();
+        let valid = |source: &str| {
+            let table = toml::from_str::<Table>(source).unwrap();
+            matches(&schema, &schema, &serde_json::to_value(table).unwrap())
+        };
+
+        let source = "[project]\nname = \"test\"\nclear_color = [0.0, 0.0, 0.0, 1.0]\nrender_scale = 0.5\ninclude_dirs = [\"lib\"]\n\n[shaders.a]\nvertex_entry = \"main\"\n\n[scenes.a]\nclear_color = [1.0, 1.0, 1.0, 1.0]\n";
+        Manifest::parse(Path::new("Kiln.toml"), source).unwrap();
+        assert!(valid(source));
+
+        assert!(valid(
+            "[project]\nname = \"test\"\nclear-color = [0.0, 0.0, 0.0, 1.0]\n"
+        ));
+        assert!(!valid(
+            "[project]\nname = \"test\"\nclear-colour = [0.0, 0.0, 0.0, 1.0]\n"
+        ));
+        assert!(!valid("[project]\nname = 1\n"));
+    }
+
+    #[test]
+    fn unknown_messages() {
+        let err = toml::from_str::<Manifest>("[project]\nname = \"test\"\nnmae = 1\n").unwrap_err();
+        assert_eq!(
+            Unknown::parse(&err.to_string()).map(|unknown| (unknown.name, unknown.table)),
+            Some(("nmae", Some("project")))
+        );
+
+        let err = toml::from_str::<Manifest>("nmae = 1\n[project]\nname = \"test\"\n").unwrap_err();
+        let message = err.to_string();
+        let unknown = Unknown::parse(&message).unwrap();
+        assert_eq!((unknown.name, unknown.table), ("nmae", None));
+        assert!(unknown.expected.contains(&"project"));
+
+        let source = "[project]\nname = \"test\"\n\n[shaders.main]\nblend = \"aditive\"\n";
+        let message = toml::from_str::<Manifest>(source).unwrap_err().to_string();
+        let unknown = Unknown::parse(&message).unwrap();
+        assert_eq!(
+            (unknown.name, unknown.table, unknown.field),
+            ("aditive", Some("shaders.main.blend"), false)
+        );
+
+        // a key of the same name in a later table isn't the unknown one
+        let source = "[camera]\nfov = 60.0\n\n[project]\nname = \"test\"\n";
+        let message = "unknown field `fov`, expected `name` for key `project`";
+        assert_eq!(locate(source, message), None);
+    }
+
+    #[test]
+    fn layers_merge_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let write = |name: &str, source: &str| {
+            let path = root.join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        };
+
+        write(
+            "shared/Kiln.toml",
+            "[project]\nname = \"shared\"\ninclude_dirs = [\"../lib\"]\n\n[shaders.a]\nfragment = \"a.wgsl\"\nblend = \"alpha\"\n\n[shaders.b]\nfragment = \"b.wgsl\"\n",
+        );
+        write(
+            "project/passes/c.toml",
+            "[shaders.c]\nfragment = \"c.wgsl\"\n",
+        );
+        write(
+            "project/Kiln.toml",
+            "extends = \"../shared/Kiln.toml\"\ninclude = [\"passes/*.toml\"]\n\n[project]\nname = \"project\"\n\n[shaders.a]\nfragment = \"a.wgsl\"\n",
+        );
+
+        let manifest = Manifest::load(&root.join("project/Kiln.toml")).unwrap();
+        let include_dirs = &manifest.project.include_dirs;
+        let lib = root.canonicalize().unwrap().join("shared/../lib");
+        assert_eq!(include_dirs, &[lib]);
+
+        assert_eq!(manifest.project.name, "project");
+        assert_eq!(manifest.sources.len(), 3);
+        assert_eq!(manifest.shaders.keys().collect::<Vec<_>>(), ["a", "b", "c"]);
+
+        // entries merge key by key, with paths relative to their own manifest
+        let a = &manifest.shaders["a"];
+        assert_eq!(a.blend, ManifestBlend::Alpha);
+        assert_eq!(a.fragment.as_deref(), Some(Path::new("a.wgsl")));
+        assert!(manifest.shaders["b"]
+            .fragment
+            .as_ref()
+            .unwrap()
+            .ends_with("shared/b.wgsl"));
+        assert!(manifest.shaders["c"]
+            .fragment
+            .as_ref()
+            .unwrap()
+            .ends_with("project/passes/c.wgsl"));
+    }
+
+    #[test]
+    fn scenes_select_shaders() {
+        let manifest = Manifest::parse(
+            Path::new("Kiln.toml"),
+            "[project]\nname = \"test\"\n\n[shaders.a]\n[shaders.b]\n[shaders.c]\n\n[scenes.ac]\nshaders = [\"c\", \"a\"]\ncamera = { fov = 30.0 }\nclear_color = [1.0, 0.0, 0.0, 1.0]\n\n[scenes.bad]\nshaders = [\"d\"]\ncamera = { zoom = 2.0 }\n\n[camera]\nposition = [0.0, 1.0, 0.0]\n",
+        )
+        .unwrap();
+
+        let scene = manifest.scene("ac").unwrap();
+        assert_eq!(scene.shaders.keys().collect::<Vec<_>>(), ["a", "c"]);
+        assert_eq!(scene.camera.fov, 30.0);
+        assert_eq!(scene.camera.position, Vec3::Y);
+        assert_eq!(scene.camera.direction, Vec3::Z);
+        assert_eq!(scene.project.clear_color, [1.0, 0.0, 0.0, 1.0]);
+
+        assert!(matches!(
+            manifest.scene("bad"),
+            Err(ManifestError::UnknownShader(..))
+        ));
+
+        let mut bad = manifest.clone();
+        bad.scenes.get_mut("bad").unwrap().shaders = None;
+        assert!(matches!(
+            bad.scene("bad"),
+            Err(ManifestError::SceneCamera(..))
+        ));
+
+        assert!(matches!(
+            manifest.scene("none"),
+            Err(ManifestError::UnknownScene(_))
+        ));
+    }
+
+    #[test]
+    fn overrides() {
+        let mut table = toml::from_str::<Table>("[project]\nname = \"test\"\n").unwrap();
+
+        let overrides = [
+            "camera.position = [0, 500, 0]",
+            "project.clear_color=[1.0, 1.0, 1.0, 1.0]",
+            "project.author=someone else",
+        ];
+        for source in overrides {
+            source
+                .parse::<ManifestOverride>()
+                .unwrap()
+                .apply(&mut table)
+                .unwrap();
+        }
+
+        let manifest = toml::Value::Table(table).try_into::<Manifest>().unwrap();
+        assert_eq!(manifest.camera.position, Vec3::new(0.0, 500.0, 0.0));
+        assert_eq!(manifest.project.clear_color, [1.0; 4]);
+        assert_eq!(manifest.project.author.as_deref(), Some("someone else"));
+
+        assert!("camera".parse::<ManifestOverride>().is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Kiln.toml");
+        fs::write(&path, "[project]\nname = \"test\"\n").unwrap();
+
+        let overrides = ["camera.fov=60", "camera.fov=wide"].map(|source| source.parse().unwrap());
+        match Manifest::load_with_overrides(&path, &overrides) {
+            Err(Error::Manifest(ManifestError::Override(origin, _))) => {
+                assert_eq!(origin, "camera.fov=wide")
+            }
+            result => panic!("expected an override error, got {:?}", result.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn overrides_from_vars() {
+        let vars = [
+            ("KILN_PROJECT__CLEAR_COLOR", "[0, 0, 0, 1]"),
+            ("KILN_LOG", "debug"),
+            ("KILN_CAMERA__FOV", "60"),
+            ("PATH", "/usr/bin"),
+        ];
+        let overrides = ManifestOverride::from_vars(
+            vars.map(|(name, value)| (name.to_string(), value.to_string())),
+        )
+        .unwrap();
+
+        let origins = overrides
+            .iter()
+            .map(|manifest_override| manifest_override.origin.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(origins, ["KILN_CAMERA__FOV", "KILN_PROJECT__CLEAR_COLOR"]);
+
+        let mut table = toml::from_str::<Table>("[project]\nname = \"test\"\n").unwrap();
+        for manifest_override in overrides {
+            manifest_override.apply(&mut table).unwrap();
+        }
+
+        let manifest = toml::Value::Table(table).try_into::<Manifest>().unwrap();
+        assert_eq!(manifest.camera.fov, 60.0);
+        assert_eq!(manifest.project.clear_color, [0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn formats() {
+        let toml = "[project]\nname = \"test\"\n\n[shaders.b]\nfragment = \"b.wgsl\"\n\n[shaders.a]\nfragment = \"a.wgsl\"\n";
+        let json = r#"{"project": {"name": "test", "author": null}, "shaders": {"b": {"fragment": "b.wgsl"}, "a": {"fragment": "a.wgsl"}}}"#;
+        let ron = r#"(project: (name: "test", author: None), shaders: {"b": (fragment: "b.wgsl"), "a": (fragment: "a.wgsl")})"#;
+
+        // tables compare regardless of order, so compare the shader names too
+        let shaders = |table: &Table| {
+            table["shaders"]
+                .as_table()
+                .unwrap()
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let path = Path::new("Kiln.toml");
+        let table = ManifestFormat::Toml.parse(path, toml).unwrap();
+        for (format, source) in [(ManifestFormat::Json, json), (ManifestFormat::Ron, ron)] {
+            let parsed = format.parse(path, source).unwrap();
+            assert_eq!(parsed, table);
+            assert_eq!(shaders(&parsed), ["b", "a"]);
+        }
+        assert!(ManifestFormat::Json
+            .parse(path, r#"{"camera": {"position": [0, null, 0]}}"#)
+            .is_err());
+
+        for format in [
+            ManifestFormat::Toml,
+            ManifestFormat::Json,
+            ManifestFormat::Ron,
+        ] {
+            let source = format.write(&table).unwrap();
+            let parsed = format.parse(path, &source).unwrap();
+            assert_eq!(parsed, table);
+            assert_eq!(shaders(&parsed), ["b", "a"]);
+        }
+
+        // a value after a table, which toml has to write first
+        let extends = r#"{"project": {"name": "demo"}, "extends": "sub/Kiln.json"}"#;
+        let table = ManifestFormat::Json.parse(path, extends).unwrap();
+        let source = ManifestFormat::Toml.write(&table).unwrap();
+        assert_eq!(ManifestFormat::Toml.parse(path, &source).unwrap(), table);
+
+        assert_eq!(
+            ManifestFormat::of(Path::new("a/Kiln.ron")),
+            ManifestFormat::Ron
+        );
+    }
 }
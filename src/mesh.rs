@@ -0,0 +1,406 @@
+use std::{
+    f32::consts::{PI, TAU},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat3, Mat4, Vec3};
+use thiserror::Error;
+use wgpu::util::DeviceExt;
+
+use crate::manifest::ManifestShape;
+
+/// Vertex layout of every mesh, bound to vertex shaders as
+/// `@location(0) position: vec3<f32>`, `@location(1) normal: vec3<f32>` and
+/// `@location(2) uv: vec2<f32>`, see `kiln/mesh`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Pod, Zeroable)]
+pub struct Vertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+}
+
+impl Vertex {
+    const ATTRIBUTES: [wgpu::VertexAttribute; 3] =
+        wgpu::vertex_attr_array![0 => Float32x3, 1 => Float32x3, 2 => Float32x2];
+
+    pub fn layout() -> wgpu::VertexBufferLayout<'static> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<Self>() as u64,
+            step_mode: wgpu::VertexStepMode::Vertex,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+/// Triangles on the cpu, as loaded from a file.
+#[derive(Clone, Debug, Default)]
+pub struct MeshData {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+}
+
+impl MeshData {
+    /// Loads an `.obj`, `.gltf` or `.glb` file, merging all of its meshes.
+    pub fn load(path: &Path) -> Result<Self, MeshError> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase);
+
+        let mut data = match extension.as_deref() {
+            Some("obj") => Self::load_obj(path)?,
+            Some("gltf" | "glb") => Self::load_gltf(path)?,
+            _ => return Err(MeshError::UnknownFormat(path.to_path_buf())),
+        };
+
+        if data.indices.is_empty() {
+            return Err(MeshError::Empty(path.to_path_buf()));
+        }
+
+        if data.vertices.iter().all(|vertex| vertex.normal == [0.0; 3]) {
+            data.compute_normals();
+        }
+
+        Ok(data)
+    }
+
+    fn load_obj(path: &Path) -> Result<Self, MeshError> {
+        let options = tobj::LoadOptions {
+            triangulate: true,
+            single_index: true,
+            ..Default::default()
+        };
+        let (models, _) = tobj::load_obj(path, &options)?;
+
+        let mut data = Self::default();
+
+        for model in models {
+            let mesh = model.mesh;
+            let offset = data.vertices.len() as u32;
+
+            for i in 0..mesh.positions.len() / 3 {
+                let normal = mesh.normals.get(i * 3..i * 3 + 3);
+                let uv = mesh.texcoords.get(i * 2..i * 2 + 2);
+
+                data.vertices.push(Vertex {
+                    position: [
+                        mesh.positions[i * 3],
+                        mesh.positions[i * 3 + 1],
+                        mesh.positions[i * 3 + 2],
+                    ],
+                    normal: normal.map_or([0.0; 3], |n| [n[0], n[1], n[2]]),
+                    // obj puts the origin of texture coordinates at the bottom left
+                    uv: uv.map_or([0.0; 2], |uv| [uv[0], 1.0 - uv[1]]),
+                });
+            }
+
+            data.indices
+                .extend(mesh.indices.iter().map(|index| index + offset));
+        }
+
+        Ok(data)
+    }
+
+    fn load_gltf(path: &Path) -> Result<Self, MeshError> {
+        let gltf = gltf::Gltf::open(path)?;
+        let root = path.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut buffers = Vec::new();
+        for buffer in gltf.document.buffers() {
+            let data = match buffer.source() {
+                gltf::buffer::Source::Bin => gltf
+                    .blob
+                    .clone()
+                    .ok_or_else(|| MeshError::MissingBuffer(path.to_path_buf()))?,
+                gltf::buffer::Source::Uri(uri) if uri.starts_with("data:") => {
+                    return Err(MeshError::DataUri(path.to_path_buf()))
+                }
+                gltf::buffer::Source::Uri(uri) => fs::read(root.join(uri))?,
+            };
+
+            buffers.push(data);
+        }
+
+        let mut data = Self::default();
+
+        let scene = gltf
+            .document
+            .default_scene()
+            .or_else(|| gltf.document.scenes().next());
+
+        if let Some(scene) = scene {
+            for node in scene.nodes() {
+                data.load_gltf_node(&node, Mat4::IDENTITY, &buffers);
+            }
+        }
+
+        Ok(data)
+    }
+
+    fn load_gltf_node(&mut self, node: &gltf::Node, parent: Mat4, buffers: &[Vec<u8>]) {
+        let transform = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+        let normal_transform = Mat3::from_mat4(transform).inverse().transpose();
+
+        if let Some(mesh) = node.mesh() {
+            for primitive in mesh.primitives() {
+                if primitive.mode() != gltf::mesh::Mode::Triangles {
+                    continue;
+                }
+
+                let reader =
+                    primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+
+                let positions = match reader.read_positions() {
+                    Some(positions) => positions.collect::<Vec<_>>(),
+                    None => continue,
+                };
+                let normals = reader
+                    .read_normals()
+                    .map(Iterator::collect::<Vec<_>>)
+                    .unwrap_or_default();
+                let uvs = reader
+                    .read_tex_coords(0)
+                    .map(|uvs| uvs.into_f32().collect::<Vec<_>>())
+                    .unwrap_or_default();
+
+                let offset = self.vertices.len() as u32;
+
+                for (i, position) in positions.iter().enumerate() {
+                    let normal = normals.get(i).map_or(Vec3::ZERO, |normal| {
+                        (normal_transform * Vec3::from(*normal)).normalize_or_zero()
+                    });
+
+                    self.vertices.push(Vertex {
+                        position: transform.transform_point3(Vec3::from(*position)).into(),
+                        normal: normal.into(),
+                        uv: uvs.get(i).copied().unwrap_or_default(),
+                    });
+                }
+
+                match reader.read_indices() {
+                    Some(indices) => self
+                        .indices
+                        .extend(indices.into_u32().map(|index| index + offset)),
+                    None => self.indices.extend(offset..self.vertices.len() as u32),
+                }
+            }
+        }
+
+        for child in node.children() {
+            self.load_gltf_node(&child, transform, buffers);
+        }
+    }
+
+    /// Generates one of the built-in shapes, `subdivisions` controlling its resolution.
+    pub fn shape(shape: ManifestShape, subdivisions: Option<u32>) -> Self {
+        match shape {
+            ManifestShape::Sphere => Self::sphere(subdivisions.unwrap_or(32).max(3)),
+            ManifestShape::Cube => Self::cube(subdivisions.unwrap_or(1).max(1)),
+            ManifestShape::Plane => Self::plane(subdivisions.unwrap_or(1).max(1)),
+            ManifestShape::Torus => Self::torus(subdivisions.unwrap_or(48).max(3)),
+        }
+    }
+
+    /// Adds a `columns` by `rows` grid of quads, with `vertex` mapping uvs in 0..1 to a vertex.
+    fn grid(&mut self, columns: u32, rows: u32, vertex: impl Fn(f32, f32) -> Vertex) {
+        let offset = self.vertices.len() as u32;
+
+        for row in 0..=rows {
+            for column in 0..=columns {
+                let u = column as f32 / columns as f32;
+                let v = row as f32 / rows as f32;
+                self.vertices.push(vertex(u, v));
+            }
+        }
+
+        for row in 0..rows {
+            for column in 0..columns {
+                let a = offset + row * (columns + 1) + column;
+                let b = a + columns + 1;
+
+                self.indices.extend([a, b, a + 1, a + 1, b, b + 1]);
+            }
+        }
+    }
+
+    /// Unit sphere with `subdivisions` rings and twice as many segments.
+    pub fn sphere(subdivisions: u32) -> Self {
+        let mut data = Self::default();
+
+        data.grid(subdivisions * 2, subdivisions, |u, v| {
+            let theta = u * TAU;
+            let phi = v * PI;
+            let normal = Vec3::new(phi.sin() * theta.cos(), phi.cos(), -phi.sin() * theta.sin());
+
+            Vertex {
+                position: normal.into(),
+                normal: normal.into(),
+                uv: [u, v],
+            }
+        });
+
+        data
+    }
+
+    /// Cube from -1 to 1, each face split into `subdivisions` by `subdivisions` quads.
+    pub fn cube(subdivisions: u32) -> Self {
+        let mut data = Self::default();
+
+        for normal in [Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z] {
+            // tangents chosen so `right x up` points along the normal
+            let up = if normal.y == 0.0 {
+                Vec3::Y
+            } else {
+                -Vec3::Z * normal.y
+            };
+            let right = up.cross(normal);
+
+            data.grid(subdivisions, subdivisions, |u, v| Vertex {
+                position: (normal + right * (u * 2.0 - 1.0) + up * (1.0 - v * 2.0)).into(),
+                normal: normal.into(),
+                uv: [u, v],
+            });
+        }
+
+        data
+    }
+
+    /// Plane from -1 to 1 on the xz plane, facing up.
+    pub fn plane(subdivisions: u32) -> Self {
+        let mut data = Self::default();
+
+        data.grid(subdivisions, subdivisions, |u, v| Vertex {
+            position: [u * 2.0 - 1.0, 0.0, v * 2.0 - 1.0],
+            normal: [0.0, 1.0, 0.0],
+            uv: [u, v],
+        });
+
+        data
+    }
+
+    /// Torus around the y axis with a radius of 1 and a thickness of 0.25,
+    /// `subdivisions` segments around and half as many around the tube.
+    pub fn torus(subdivisions: u32) -> Self {
+        const THICKNESS: f32 = 0.25;
+
+        let mut data = Self::default();
+
+        data.grid(subdivisions, (subdivisions / 2).max(3), |u, v| {
+            let theta = u * TAU;
+            let phi = v * TAU;
+
+            let center = Vec3::new(theta.cos(), 0.0, -theta.sin());
+            let normal = center * -phi.cos() + Vec3::Y * phi.sin();
+
+            Vertex {
+                position: (center + normal * THICKNESS).into(),
+                normal: normal.into(),
+                uv: [u, v],
+            }
+        });
+
+        data
+    }
+
+    /// Replaces all normals with area weighted face normals.
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![Vec3::ZERO; self.vertices.len()];
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] =
+                [0, 1, 2].map(|i| Vec3::from(self.vertices[triangle[i] as usize].position));
+            let normal = (b - a).cross(c - a);
+
+            for &index in triangle {
+                normals[index as usize] += normal;
+            }
+        }
+
+        for (vertex, normal) in self.vertices.iter_mut().zip(normals) {
+            vertex.normal = normal.normalize_or_zero().into();
+        }
+    }
+}
+
+/// Vertex and index buffers of a mesh.
+#[derive(Debug)]
+pub struct Mesh {
+    pub vertex_buffer: wgpu::Buffer,
+    pub index_buffer: wgpu::Buffer,
+    pub index_count: u32,
+}
+
+impl Mesh {
+    pub fn new(device: &wgpu::Device, data: &MeshData) -> Self {
+        let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("kiln-mesh-vertex-buffer"),
+            contents: bytemuck::cast_slice(&data.vertices),
+            usage: wgpu::BufferUsages::VERTEX,
+        });
+
+        let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("kiln-mesh-index-buffer"),
+            contents: bytemuck::cast_slice(&data.indices),
+            usage: wgpu::BufferUsages::INDEX,
+        });
+
+        Self {
+            vertex_buffer,
+            index_buffer,
+            index_count: data.indices.len() as u32,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MeshError {
+    #[error("unknown mesh format '{0}', expected obj, gltf or glb")]
+    UnknownFormat(PathBuf),
+    #[error("mesh '{0}' has no triangles")]
+    Empty(PathBuf),
+    #[error("'{0}' references a binary chunk it doesn't contain")]
+    MissingBuffer(PathBuf),
+    #[error("'{0}' embeds buffers as data uris, which aren't supported, use a .glb instead")]
+    DataUri(PathBuf),
+    #[error("failed to load obj: {0}")]
+    Obj(#[from] tobj::LoadError),
+    #[error("failed to load gltf: {0}")]
+    Gltf(#[from] gltf::Error),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shapes_face_outwards() {
+        for shape in [
+            ManifestShape::Sphere,
+            ManifestShape::Cube,
+            ManifestShape::Plane,
+            ManifestShape::Torus,
+        ] {
+            let data = MeshData::shape(shape, Some(8));
+            assert_eq!(data.indices.len() % 3, 0);
+
+            for triangle in data.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| data.vertices[triangle[i] as usize]);
+                let [pa, pb, pc] = [a, b, c].map(|vertex| Vec3::from(vertex.position));
+                let face = (pb - pa).cross(pc - pa);
+
+                // triangles at the poles of the sphere collapse to lines
+                if face.length() < 1e-6 {
+                    continue;
+                }
+
+                let normal = Vec3::from(a.normal) + Vec3::from(b.normal) + Vec3::from(c.normal);
+                assert!(face.dot(normal) > 0.0, "{:?} is wound inwards", shape);
+            }
+        }
+    }
+}
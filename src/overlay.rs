@@ -0,0 +1,343 @@
+use bytemuck::{Pod, Zeroable};
+use wgpu::util::DeviceExt;
+
+/// Red border drawn over the window while the manifest fails to load, with
+/// the error written inside it.
+#[derive(Debug)]
+pub struct ErrorOverlay {
+    pub format: wgpu::TextureFormat,
+    pub pipeline: wgpu::RenderPipeline,
+    pub bind_group: wgpu::BindGroup,
+    pub text_buffer: wgpu::Buffer,
+    /// Message and window width the text buffer was laid out for.
+    text: Option<(String, u32)>,
+}
+
+/// Text of the overlay as laid out for the shader.
+#[repr(C)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+struct OverlayText {
+    /// Ascii characters, row by row and four to a u32.
+    chars: [[u32; 4]; OverlayText::MAX_CHARS / 16],
+    columns: u32,
+    rows: u32,
+    /// Size of a font pixel in window pixels.
+    scale: u32,
+    _padding: u32,
+}
+
+impl OverlayText {
+    const MAX_CHARS: usize = 1024;
+    const MAX_COLUMNS: usize = 128;
+    const MAX_ROWS: usize = Self::MAX_CHARS / Self::MAX_COLUMNS;
+    /// Width of a glyph and the space after it, in font pixels.
+    const CELL_WIDTH: u32 = 6;
+    /// Space left of the text, in window pixels, must match `TEXT_OFFSET` in overlay.wgsl.
+    const OFFSET: u32 = 24;
+
+    /// `message` wrapped to fit a window `width` pixels wide.
+    fn new(message: &str, width: u32) -> Self {
+        let scale = if width >= 1280 { 2 } else { 1 };
+        let fit = width.saturating_sub(Self::OFFSET * 2) / (Self::CELL_WIDTH * scale);
+        let columns = (fit as usize).clamp(1, Self::MAX_COLUMNS);
+
+        let mut rows = Vec::new();
+        for line in message.lines() {
+            let line = line
+                .chars()
+                .map(|c| match c {
+                    '\t' => ' ',
+                    c if c.is_ascii_graphic() || c == ' ' => c,
+                    _ => '?',
+                })
+                .collect::<Vec<_>>();
+
+            rows.extend(line.chunks(columns).map(<[char]>::to_vec));
+        }
+
+        if rows.len() > Self::MAX_ROWS {
+            rows.truncate(Self::MAX_ROWS);
+            let last = rows.last_mut().unwrap();
+            last.truncate(columns.saturating_sub(3));
+            last.extend(['.'; 3]);
+        }
+
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut bytes = [0u8; Self::MAX_CHARS];
+        for (row, line) in rows.iter().enumerate() {
+            for (column, c) in line.iter().enumerate() {
+                bytes[row * columns + column] = *c as u8;
+            }
+        }
+
+        let mut chars = [[0; 4]; Self::MAX_CHARS / 16];
+        for (i, word) in bytes.chunks(4).enumerate() {
+            chars[i / 4][i % 4] = u32::from_le_bytes(word.try_into().unwrap());
+        }
+
+        Self {
+            chars,
+            columns: columns as u32,
+            rows: rows.len() as u32,
+            scale,
+            _padding: 0,
+        }
+    }
+}
+
+impl ErrorOverlay {
+    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("kiln-overlay-shader"),
+            source: wgpu::ShaderSource::Wgsl(include_str!("overlay.wgsl").into()),
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("kiln-overlay-pipeline"),
+            layout: None,
+            vertex: wgpu::VertexState {
+                module: &module,
+                entry_point: "vert",
+                buffers: &[],
+            },
+            primitive: Default::default(),
+            depth_stencil: None,
+            multisample: Default::default(),
+            fragment: Some(wgpu::FragmentState {
+                module: &module,
+                entry_point: "frag",
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+        });
+
+        let text_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("kiln-overlay-text-buffer"),
+            size: std::mem::size_of::<OverlayText>() as u64,
+            mapped_at_creation: false,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
+        });
+
+        // two glyphs to a vec4, padded to a whole number of them
+        let mut glyphs = FONT.to_vec();
+        glyphs.resize(FONT.len().next_multiple_of(2), 0);
+        let font_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("kiln-overlay-font-buffer"),
+            contents: bytemuck::cast_slice(&glyphs),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("kiln-overlay-group"),
+            layout: &pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: text_buffer.as_entire_binding(),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: font_buffer.as_entire_binding(),
+                },
+            ],
+        });
+
+        Self {
+            format,
+            pipeline,
+            bind_group,
+            text_buffer,
+            text: None,
+        }
+    }
+
+    /// Draws the border and `message` over a `width` pixel wide `target_view`.
+    pub fn draw(
+        &mut self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target_view: &wgpu::TextureView,
+        message: &str,
+        width: u32,
+    ) {
+        let laid_out = self
+            .text
+            .as_ref()
+            .is_some_and(|(text, text_width)| text == message && *text_width == width);
+        if !laid_out {
+            let text = OverlayText::new(message, width);
+            queue.write_buffer(&self.text_buffer, 0, bytemuck::bytes_of(&text));
+            self.text = Some((message.to_string(), width));
+        }
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("kiln-overlay-pass"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: target_view,
+                resolve_target: None,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            })],
+            depth_stencil_attachment: None,
+        });
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(0, &self.bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+    }
+}
+
+/// 5x7 glyphs of ascii 32 to 126, a row to a byte from the top one in the
+/// lowest byte, with the leftmost pixel in bit 4.
+const FONT: [u64; 95] = [
+    0x00_00_00_00_00_00_00, // space
+    0x04_00_04_04_04_04_04, // !
+    0x00_00_00_00_0a_0a_0a, // "
+    0x0a_0a_1f_0a_1f_0a_0a, // #
+    0x04_1e_05_0e_14_0f_04, // $
+    0x03_13_08_04_02_19_18, // %
+    0x0d_12_15_08_14_12_0c, // &
+    0x00_00_00_00_04_04_04, // '
+    0x02_04_08_08_08_04_02, // (
+    0x08_04_02_02_02_04_08, // )
+    0x00_04_15_0e_15_04_00, // *
+    0x00_04_04_1f_04_04_00, // +
+    0x08_04_0c_00_00_00_00, // ,
+    0x00_00_00_1f_00_00_00, // -
+    0x0c_0c_00_00_00_00_00, // .
+    0x00_10_08_04_02_01_00, // /
+    0x0e_11_19_15_13_11_0e, // 0
+    0x0e_04_04_04_04_0c_04, // 1
+    0x1f_08_04_02_01_11_0e, // 2
+    0x0e_11_01_02_04_02_1f, // 3
+    0x02_02_1f_12_0a_06_02, // 4
+    0x0e_11_01_01_1e_10_1f, // 5
+    0x0e_11_11_1e_10_08_06, // 6
+    0x08_08_08_04_02_01_1f, // 7
+    0x0e_11_11_0e_11_11_0e, // 8
+    0x0c_02_01_0f_11_11_0e, // 9
+    0x00_0c_0c_00_0c_0c_00, // :
+    0x08_04_0c_00_0c_0c_00, // ;
+    0x02_04_08_10_08_04_02, // <
+    0x00_00_1f_00_1f_00_00, // =
+    0x08_04_02_01_02_04_08, // >
+    0x04_00_04_02_01_11_0e, // ?
+    0x0e_15_15_0d_01_11_0e, // @
+    0x11_11_11_1f_11_11_0e, // A
+    0x1e_11_11_1e_11_11_1e, // B
+    0x0e_11_10_10_10_11_0e, // C
+    0x1c_12_11_11_11_12_1c, // D
+    0x1f_10_10_1e_10_10_1f, // E
+    0x10_10_10_1e_10_10_1f, // F
+    0x0f_11_11_17_10_11_0e, // G
+    0x11_11_11_1f_11_11_11, // H
+    0x0e_04_04_04_04_04_0e, // I
+    0x0c_12_02_02_02_02_07, // J
+    0x11_12_14_18_14_12_11, // K
+    0x1f_10_10_10_10_10_10, // L
+    0x11_11_11_15_15_1b_11, // M
+    0x11_11_13_15_19_11_11, // N
+    0x0e_11_11_11_11_11_0e, // O
+    0x10_10_10_1e_11_11_1e, // P
+    0x0d_12_15_11_11_11_0e, // Q
+    0x11_12_14_1e_11_11_1e, // R
+    0x1e_01_01_0e_10_10_0f, // S
+    0x04_04_04_04_04_04_1f, // T
+    0x0e_11_11_11_11_11_11, // U
+    0x04_0a_11_11_11_11_11, // V
+    0x0a_15_15_15_11_11_11, // W
+    0x11_11_0a_04_0a_11_11, // X
+    0x04_04_04_04_0a_11_11, // Y
+    0x1f_10_08_04_02_01_1f, // Z
+    0x0e_08_08_08_08_08_0e, // [
+    0x00_01_02_04_08_10_00, // \
+    0x0e_02_02_02_02_02_0e, // ]
+    0x00_00_00_00_11_0a_04, // ^
+    0x1f_00_00_00_00_00_00, // _
+    0x00_00_00_00_02_04_08, // `
+    0x0f_11_0f_01_0e_00_00, // a
+    0x1e_11_11_19_16_10_10, // b
+    0x0e_11_10_10_0e_00_00, // c
+    0x0f_11_11_13_0d_01_01, // d
+    0x0e_10_1f_11_0e_00_00, // e
+    0x08_08_08_1c_08_09_06, // f
+    0x0e_01_0f_11_11_0f_00, // g
+    0x11_11_11_19_16_10_10, // h
+    0x0e_04_04_04_0c_00_04, // i
+    0x0c_12_02_02_06_00_02, // j
+    0x12_14_18_14_12_10_10, // k
+    0x0e_04_04_04_04_04_0c, // l
+    0x11_11_15_15_1a_00_00, // m
+    0x11_11_11_19_16_00_00, // n
+    0x0e_11_11_11_0e_00_00, // o
+    0x10_10_1e_11_1e_00_00, // p
+    0x01_01_0f_13_0d_00_00, // q
+    0x10_10_10_19_16_00_00, // r
+    0x1e_01_0e_10_0e_00_00, // s
+    0x06_09_08_08_1c_08_08, // t
+    0x0d_13_11_11_11_00_00, // u
+    0x04_0a_11_11_11_00_00, // v
+    0x0a_15_15_11_11_00_00, // w
+    0x11_0a_04_0a_11_00_00, // x
+    0x0e_01_0f_11_11_00_00, // y
+    0x1f_08_04_02_1f_00_00, // z
+    0x02_04_04_08_04_04_02, // {
+    0x04_04_04_04_04_04_04, // |
+    0x08_04_04_02_04_04_08, // }
+    0x00_00_02_15_08_00_00, // ~
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shader_is_valid() {
+        let source = include_str!("overlay.wgsl");
+        let module = naga::front::wgsl::parse_str(source)
+            .unwrap_or_else(|err| panic!("{}", err.emit_to_string(source)));
+
+        naga::valid::Validator::new(
+            naga::valid::ValidationFlags::all(),
+            naga::valid::Capabilities::empty(),
+        )
+        .validate(&module)
+        .unwrap();
+
+        // the uniform sizes the shader expects
+        let size = |name: &str| {
+            let (_, var) = module
+                .global_variables
+                .iter()
+                .find(|(_, var)| var.name.as_deref() == Some(name))
+                .unwrap();
+            module.types[var.ty].inner.size(&module.constants) as usize
+        };
+        assert_eq!(size("text"), std::mem::size_of::<OverlayText>());
+        assert_eq!(size("font"), FONT.len().next_multiple_of(2) * 8);
+    }
+
+    #[test]
+    fn text_wraps_to_the_window() {
+        let text = OverlayText::new("ab\tc\nsecond line é", 24 * 2 + 6 * 4);
+        assert_eq!(text.columns, 4);
+        assert_eq!(text.rows, 5);
+        assert_eq!(text.scale, 1);
+        let bytes = bytemuck::bytes_of(&text.chars);
+        assert_eq!(&bytes[..20], b"ab csecond line ?\0\0\0");
+
+        let long = "x\n".repeat(20);
+        let text = OverlayText::new(&long, 2000);
+        assert_eq!(text.rows as usize, OverlayText::MAX_ROWS);
+        assert_eq!(text.columns, 4);
+        assert_eq!(text.scale, 2);
+        let end = OverlayText::MAX_ROWS * 4;
+        assert_eq!(&bytemuck::bytes_of(&text.chars)[end - 4..end], b"x...");
+    }
+}
@@ -0,0 +1,101 @@
+struct VertexOutput {
+	@builtin(position) position: vec4<f32>,
+	@location(0) uv: vec2<f32>,
+};
+
+struct Text {
+	// ascii characters, row by row and four to a u32
+	chars: array<vec4<u32>, 64>,
+	columns: u32,
+	rows: u32,
+	// size of a font pixel in window pixels
+	scale: u32,
+};
+
+struct Font {
+	// 5x7 glyphs from ascii 32, two to a vec4
+	glyphs: array<vec4<u32>, 48>,
+};
+
+@group(0) @binding(0)
+var<uniform> text: Text;
+@group(0) @binding(1)
+var<uniform> font: Font;
+
+@vertex
+fn vert(@builtin(vertex_index) index: u32) -> VertexOutput {
+	let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+	var out: VertexOutput;
+
+	out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+	out.uv = uv;
+
+	return out;
+}
+
+// width of the border in pixels
+let BORDER: f32 = 6.0;
+// distance of the text from the top left corner in pixels, with the box around it
+let TEXT_OFFSET: i32 = 24;
+let TEXT_PADDING: i32 = 8;
+// size of a glyph and the space around it in font pixels
+let CELL_WIDTH: i32 = 6;
+let CELL_HEIGHT: i32 = 9;
+
+fn character(index: u32) -> u32 {
+	let word = text.chars[index / 16u][(index / 4u) % 4u];
+	return (word >> ((index % 4u) * 8u)) & 0xffu;
+}
+
+// whether pixel x, y of the glyph of character c is set
+fn glyph(c: u32, x: u32, y: u32) -> bool {
+	if (c < 32u || c > 126u || x >= 5u || y >= 7u) {
+		return false;
+	}
+
+	let index = c - 32u;
+	let glyphs = font.glyphs[index / 2u];
+	var rows = glyphs.xy;
+	if (index % 2u == 1u) {
+		rows = glyphs.zw;
+	}
+
+	var row = rows.x >> (y * 8u);
+	if (y >= 4u) {
+		row = rows.y >> ((y - 4u) * 8u);
+	}
+
+	return ((row >> (4u - x)) & 1u) == 1u;
+}
+
+@fragment
+fn frag(input: VertexOutput) -> @location(0) vec4<f32> {
+	// derivatives have to be taken before any branch
+	let edge = min(input.uv, 1.0 - input.uv) / fwidth(input.uv);
+
+	let scale = i32(text.scale);
+	let cell = vec2<i32>(CELL_WIDTH, CELL_HEIGHT) * scale;
+	let size = vec2<i32>(i32(text.columns), i32(text.rows)) * cell;
+	let position = vec2<i32>(input.position.xy) - vec2<i32>(TEXT_OFFSET);
+
+	if (text.rows > 0u && all(position >= vec2<i32>(-TEXT_PADDING)) && all(position < size + TEXT_PADDING)) {
+		if (all(position >= vec2<i32>(0)) && all(position < size)) {
+			let column = position / cell;
+			let pixel = (position % cell) / scale;
+			let c = character(u32(column.y) * text.columns + u32(column.x));
+
+			if (glyph(c, u32(pixel.x), u32(pixel.y - 1))) {
+				return vec4<f32>(1.0, 0.9, 0.9, 1.0);
+			}
+		}
+
+		return vec4<f32>(0.15, 0.02, 0.02, 0.85);
+	}
+
+	if (min(edge.x, edge.y) > BORDER) {
+		discard;
+	}
+
+	return vec4<f32>(0.9, 0.1, 0.1, 0.8);
+}
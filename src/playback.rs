@@ -0,0 +1,154 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use rodio::{OutputStream, Sink, Source};
+
+use crate::audio::AudioClip;
+
+/// Seek requests further than this from the playing position are applied.
+const MAX_DRIFT: f64 = 0.05;
+const NO_SEEK: u64 = u64::MAX;
+
+#[derive(Debug)]
+struct PlaybackState {
+    playing: AtomicBool,
+    /// Frame the source is currently at.
+    position: AtomicU64,
+    /// Frame the source should jump to, or `NO_SEEK`.
+    seek: AtomicU64,
+}
+
+/// Endless source reading from a clip, silent while paused or past its end.
+struct ClipSource {
+    clip: Arc<AudioClip>,
+    state: Arc<PlaybackState>,
+    frame: u64,
+    channel: usize,
+}
+
+impl Iterator for ClipSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if self.channel == 0 {
+            let seek = self.state.seek.swap(NO_SEEK, Ordering::Relaxed);
+            if seek != NO_SEEK {
+                self.frame = seek;
+            }
+        }
+
+        if !self.state.playing.load(Ordering::Relaxed) {
+            return Some(0.0);
+        }
+
+        let index = self.frame as usize * self.clip.channels + self.channel;
+        let sample = self.clip.samples.get(index).copied().unwrap_or(0.0);
+
+        self.channel += 1;
+        if self.channel == self.clip.channels {
+            self.channel = 0;
+            self.frame += 1;
+            self.state.position.store(self.frame, Ordering::Relaxed);
+        }
+
+        Some(sample)
+    }
+}
+
+impl Source for ClipSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        self.clip.channels as u16
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.clip.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Plays an audio clip on the default output device, following the animation clock.
+///
+/// Without an output device the clip is silently skipped.
+pub struct Playback {
+    pub clip: Arc<AudioClip>,
+    pub volume: f32,
+    state: Arc<PlaybackState>,
+    output: Option<(OutputStream, Sink)>,
+}
+
+impl Playback {
+    pub fn new(clip: Arc<AudioClip>, volume: f32) -> Self {
+        let state = Arc::new(PlaybackState {
+            playing: AtomicBool::new(false),
+            position: AtomicU64::new(0),
+            seek: AtomicU64::new(NO_SEEK),
+        });
+
+        let source = ClipSource {
+            clip: clip.clone(),
+            state: state.clone(),
+            frame: 0,
+            channel: 0,
+        };
+
+        let output = OutputStream::try_default()
+            .map_err(|err| err.to_string())
+            .and_then(|(stream, handle)| {
+                let sink = Sink::try_new(&handle).map_err(|err| err.to_string())?;
+                sink.set_volume(volume);
+                sink.append(source);
+
+                Ok((stream, sink))
+            });
+
+        let output = match output {
+            Ok(output) => Some(output),
+            Err(err) => {
+                eprintln!("failed to open audio output, running silently: {}", err);
+                None
+            }
+        };
+
+        Self {
+            clip,
+            volume,
+            state,
+            output,
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        if let Some((_, ref sink)) = self.output {
+            sink.set_volume(volume);
+        }
+
+        self.volume = volume;
+    }
+
+    /// Keeps playback within a few milliseconds of `time`, playing only when
+    /// the clock runs in real time.
+    pub fn sync(&self, time: f64, playing: bool) {
+        let rate = self.clip.sample_rate as f64;
+        let position = self.state.position.load(Ordering::Relaxed) as f64 / rate;
+
+        if (position - time).abs() > MAX_DRIFT || !playing {
+            let frame = (time * rate) as u64;
+            self.state.seek.store(frame, Ordering::Relaxed);
+            self.state.position.store(frame, Ordering::Relaxed);
+        }
+
+        self.state.playing.store(playing, Ordering::Relaxed);
+    }
+}
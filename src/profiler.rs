@@ -0,0 +1,197 @@
+use std::{
+    collections::VecDeque,
+    sync::mpsc,
+    time::{Duration, Instant},
+};
+
+use linked_hash_map::LinkedHashMap;
+
+const MAX_PASSES: u32 = 64;
+const SAMPLES: usize = 60;
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, Default)]
+struct RollingAverage {
+    samples: VecDeque<f64>,
+}
+
+impl RollingAverage {
+    fn push(&mut self, sample: f64) {
+        if self.samples.len() == SAMPLES {
+            self.samples.pop_front();
+        }
+
+        self.samples.push_back(sample);
+    }
+
+    fn average(&self) -> f64 {
+        self.samples.iter().sum::<f64>() / self.samples.len().max(1) as f64
+    }
+}
+
+struct TimestampQueries {
+    query_set: wgpu::QuerySet,
+    read_buffer: wgpu::Buffer,
+    period: f32,
+}
+
+/// Times each pass of a frame and periodically prints a rolling average.
+///
+/// Uses timestamp queries when the device supports them, otherwise only the
+/// cpu frame time is reported. Reading queries back waits for the gpu every
+/// frame, so frame rates are slightly lower while profiling.
+pub struct Profiler {
+    queries: Option<TimestampQueries>,
+    labels: Vec<String>,
+    /// Passes begun past `MAX_PASSES` this frame, whose ends aren't recorded either.
+    skipped: u32,
+    passes: LinkedHashMap<String, RollingAverage>,
+    frame: RollingAverage,
+    last_frame: Instant,
+    last_report: Instant,
+}
+
+impl Profiler {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> Self {
+        let queries = if device.features().contains(wgpu::Features::TIMESTAMP_QUERY) {
+            let size = MAX_PASSES as u64 * 2 * 8;
+
+            Some(TimestampQueries {
+                query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                    label: Some("kiln-profiler-queries"),
+                    ty: wgpu::QueryType::Timestamp,
+                    count: MAX_PASSES * 2,
+                }),
+                read_buffer: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("kiln-profiler-read-buffer"),
+                    size,
+                    usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+                    mapped_at_creation: false,
+                }),
+                period: queue.get_timestamp_period(),
+            })
+        } else {
+            println!("timestamp queries not supported, profiling cpu frame time only");
+            None
+        };
+
+        Self {
+            queries,
+            labels: Vec::new(),
+            skipped: 0,
+            passes: LinkedHashMap::new(),
+            frame: RollingAverage::default(),
+            last_frame: Instant::now(),
+            last_report: Instant::now(),
+        }
+    }
+
+    /// Marks the start of a pass labeled `label`.
+    pub fn begin(&mut self, encoder: &mut wgpu::CommandEncoder, label: &str) {
+        if let Some(ref queries) = self.queries {
+            let index = self.labels.len() as u32;
+
+            if index < MAX_PASSES {
+                encoder.write_timestamp(&queries.query_set, index * 2);
+                self.labels.push(String::from(label));
+            } else {
+                self.skipped += 1;
+            }
+        }
+    }
+
+    /// Marks the end of the pass last started with [`Profiler::begin`].
+    pub fn end(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if let Some(ref queries) = self.queries {
+            let index = self.labels.len() as u32;
+
+            if self.skipped > 0 {
+                self.skipped -= 1;
+            } else if index > 0 {
+                encoder.write_timestamp(&queries.query_set, index * 2 - 1);
+            }
+        }
+    }
+
+    /// Resolves this frame's timestamps into a buffer that can be read back.
+    pub fn resolve(&mut self, encoder: &mut wgpu::CommandEncoder) {
+        if let Some(ref queries) = self.queries {
+            let count = self.labels.len() as u32 * 2;
+
+            if count > 0 {
+                encoder.resolve_query_set(&queries.query_set, 0..count, &queries.read_buffer, 0);
+            }
+        }
+    }
+
+    /// Reads back the timestamps of a submitted frame, printing a report when due.
+    pub fn end_frame(&mut self, device: &wgpu::Device) {
+        let now = Instant::now();
+        self.frame
+            .push((now - self.last_frame).as_secs_f64() * 1000.0);
+        self.last_frame = now;
+
+        let labels = std::mem::take(&mut self.labels);
+        self.skipped = 0;
+
+        if let (Some(queries), false) = (&self.queries, labels.is_empty()) {
+            let slice = queries.read_buffer.slice(..labels.len() as u64 * 16);
+            let (sender, receiver) = mpsc::channel();
+            slice.map_async(wgpu::MapMode::Read, move |result| {
+                let _ = sender.send(result);
+            });
+            device.poll(wgpu::Maintain::Wait);
+
+            if let Ok(Ok(())) = receiver.recv() {
+                let data = slice.get_mapped_range();
+                let timestamps: &[u64] = bytemuck::cast_slice(&data);
+
+                for (label, pass) in labels.iter().zip(timestamps.chunks(2)) {
+                    let ticks = pass[1].saturating_sub(pass[0]);
+                    let ms = ticks as f64 * queries.period as f64 / 1_000_000.0;
+
+                    if !self.passes.contains_key(label) {
+                        self.passes.insert(label.clone(), RollingAverage::default());
+                    }
+
+                    self.passes.get_mut(label).unwrap().push(ms);
+                }
+
+                drop(data);
+                queries.read_buffer.unmap();
+            }
+
+            let removed = self
+                .passes
+                .keys()
+                .filter(|label| !labels.contains(label))
+                .cloned()
+                .collect::<Vec<_>>();
+
+            for label in removed {
+                self.passes.remove(&label);
+            }
+        }
+
+        if now - self.last_report >= REPORT_INTERVAL {
+            self.last_report = now;
+            self.report();
+        }
+    }
+
+    fn report(&self) {
+        let frame = self.frame.average();
+        let mut report = format!("frame {:.2}ms ({:.0} fps)", frame, 1000.0 / frame);
+
+        if !self.passes.is_empty() {
+            let total: f64 = self.passes.values().map(RollingAverage::average).sum();
+            report += &format!(" | gpu {:.2}ms", total);
+
+            for (label, pass) in self.passes.iter() {
+                report += &format!(", {} {:.2}ms", label, pass.average());
+            }
+        }
+
+        println!("{}", report);
+    }
+}
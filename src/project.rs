@@ -0,0 +1,594 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use futures_lite::future;
+use linked_hash_map::LinkedHashMap;
+
+use crate::{
+    audio::Audio,
+    dependency::{Dependency, Lockfile},
+    error::{Error, Result},
+    manifest::{
+        Manifest, ManifestAudio, ManifestDependency, ManifestInstances, ManifestLoad,
+        ManifestOverride,
+    },
+    profiler::Profiler,
+    shader::{Shader, ShaderKind, ShaderPipeline, ShaderUniforms, DEPTH_FORMAT},
+    shader_processor::ShaderProcessor,
+    storage::StorageBuffers,
+};
+
+/// Depth buffer and, with msaa, the multisampled colour target resolved into
+/// the output.
+#[derive(Debug)]
+pub struct RenderTargets {
+    pub width: u32,
+    pub height: u32,
+    pub format: wgpu::TextureFormat,
+    pub samples: u32,
+    pub depth: wgpu::TextureView,
+    pub color: Option<wgpu::TextureView>,
+}
+
+/// A loaded kiln project, its shaders and the gpu state they share.
+pub struct Project {
+    pub path: PathBuf,
+    pub manifest_path: PathBuf,
+    /// Manifest with the selected scene applied.
+    pub manifest: Manifest,
+    /// Manifest as loaded, before selecting a scene.
+    pub loaded: Manifest,
+    pub scene: Option<String>,
+    /// Applied to the manifest every time it's loaded.
+    pub overrides: Vec<ManifestOverride>,
+    pub last_modified: SystemTime,
+    pub processor: ShaderProcessor,
+    /// Include directories searched before the manifest's, eg. from `-I`.
+    pub include_dirs: Vec<PathBuf>,
+    /// Dependencies the shader processor's exports were resolved from, cleared
+    /// on reload so they're resolved and checked against `Kiln.lock` again.
+    /// Library files edited in between are hot reloaded like the project's own.
+    pub dependencies: Option<LinkedHashMap<String, ManifestDependency>>,
+    pub storage: Option<StorageBuffers>,
+    pub audio: Option<Audio>,
+    pub shaders: LinkedHashMap<String, Shader>,
+    pub targets: Option<RenderTargets>,
+    pub format: Option<wgpu::TextureFormat>,
+    pub rebuild: bool,
+    pub invalid: bool,
+}
+
+/// Options for loading a [`Project`], see [`Project::builder`].
+#[derive(Clone, Debug)]
+pub struct ProjectBuilder {
+    path: PathBuf,
+    include_dirs: Vec<PathBuf>,
+    scene: Option<String>,
+    overrides: Vec<ManifestOverride>,
+}
+
+impl ProjectBuilder {
+    /// Directories searched for `#include <...>` globals before those of the manifest.
+    pub fn include_dirs(mut self, include_dirs: Vec<PathBuf>) -> Self {
+        self.include_dirs = include_dirs;
+        self
+    }
+
+    /// Scene of the manifest to select, every shader without one.
+    pub fn scene(mut self, scene: Option<String>) -> Self {
+        self.scene = scene;
+        self
+    }
+
+    /// Manifest values replaced every time the manifest is loaded, in order.
+    pub fn overrides(mut self, overrides: Vec<ManifestOverride>) -> Self {
+        self.overrides = overrides;
+        self
+    }
+
+    pub fn load(self) -> Result<Project> {
+        let mut project = Project::load(&self.path, self.overrides)?;
+        project.include_dirs = self.include_dirs;
+        project.set_scene(self.scene)?;
+
+        Ok(project)
+    }
+}
+
+impl Project {
+    /// Starts loading the project in the directory `path`, eg.
+    ///
+    /// ```no_run
+    /// let project = kiln::Project::builder("examples/forest")
+    ///     .overrides(vec!["camera.fov=60".parse()?])
+    ///     .load()?;
+    /// # Ok::<(), kiln::Error>(())
+    /// ```
+    pub fn builder(path: impl Into<PathBuf>) -> ProjectBuilder {
+        ProjectBuilder {
+            path: path.into(),
+            include_dirs: Vec::new(),
+            scene: None,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Loads the project in `path`, overriding values of its manifest with `overrides`.
+    pub fn load(path: &Path, overrides: Vec<ManifestOverride>) -> Result<Self> {
+        let manifest_path = Manifest::find(path);
+        let manifest = Manifest::load_with_overrides(&manifest_path, &overrides)?;
+        let last_modified = Self::manifest_modified(&manifest)?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            manifest_path,
+            manifest: manifest.clone(),
+            loaded: manifest,
+            scene: None,
+            overrides,
+            last_modified,
+            processor: ShaderProcessor::new(),
+            include_dirs: Vec::new(),
+            dependencies: None,
+            storage: None,
+            audio: None,
+            shaders: LinkedHashMap::new(),
+            targets: None,
+            format: None,
+            rebuild: true,
+            invalid: false,
+        })
+    }
+
+    /// Latest modification of the manifest or any manifest it extends or includes.
+    fn manifest_modified(manifest: &Manifest) -> Result<SystemTime> {
+        let mut modified = SystemTime::UNIX_EPOCH;
+
+        for source in manifest.sources.iter() {
+            modified = modified.max(source.metadata()?.modified()?);
+        }
+
+        Ok(modified)
+    }
+
+    /// Reloads the manifest if it, or a manifest it extends or includes, has
+    /// been modified since it was last loaded.
+    pub fn reload(&mut self) -> Result<bool> {
+        let modified = Self::manifest_modified(&self.manifest)?;
+
+        if modified > self.last_modified {
+            self.last_modified = modified;
+            self.loaded = Manifest::load_with_overrides(&self.manifest_path, &self.overrides)?;
+            self.set_scene(self.scene.clone())?;
+            self.dependencies = None;
+
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Selects scene `name` of the manifest, or every shader without one.
+    pub fn set_scene(&mut self, scene: Option<String>) -> Result<()> {
+        self.manifest = match scene {
+            Some(ref name) => self.loaded.scene(name)?,
+            None => self.loaded.clone(),
+        };
+        self.scene = scene;
+
+        Ok(())
+    }
+
+    /// Selects the scene after the current one, going back to the full project
+    /// after the last.
+    pub fn next_scene(&mut self) -> Result<()> {
+        if self.loaded.scenes.is_empty() {
+            return Ok(());
+        }
+
+        let scenes = self.loaded.scenes.keys().collect::<Vec<_>>();
+        let next = match self.scene {
+            Some(ref scene) => scenes
+                .iter()
+                .position(|&name| name == scene)
+                .and_then(|index| scenes.get(index + 1)),
+            None => scenes.first(),
+        };
+
+        self.set_scene(next.map(|&next| next.clone()))
+    }
+
+    /// Include directories of the command line followed by those of the manifest.
+    pub fn all_include_dirs(&self) -> Vec<PathBuf> {
+        let manifest_dirs = self.manifest.project.include_dirs.iter();
+
+        self.include_dirs
+            .iter()
+            .cloned()
+            .chain(manifest_dirs.map(|dir| self.path.join(dir)))
+            .collect()
+    }
+
+    /// Points the shader processor at the exports of the dependencies and the
+    /// include directories, returning whether they changed.
+    pub fn update_processor(&mut self) -> Result<bool> {
+        let mut updated = false;
+
+        if self.dependencies.as_ref() != Some(&self.manifest.dependencies) {
+            let dependencies = Dependency::resolve_all(&self.path, &self.manifest)?;
+            Lockfile::verify(&self.path, &dependencies)?;
+
+            updated |= self
+                .processor
+                .set_exports(Dependency::exports(&dependencies));
+            self.dependencies = Some(self.manifest.dependencies.clone());
+        }
+
+        let include_dirs = self.all_include_dirs();
+        updated |= self.processor.set_include_dirs(include_dirs);
+
+        Ok(updated)
+    }
+
+    pub fn uniforms(&self, width: u32, height: u32, time: f32) -> ShaderUniforms {
+        let camera = &self.manifest.camera;
+        let projection = camera.projection(width as f32 / height as f32);
+
+        ShaderUniforms::new(width, height, time, camera.view(), projection)
+    }
+
+    /// Resizes the depth and multisampled targets to match a `width` by `height`
+    /// output, call after [`Project::update`] and before [`Project::render`].
+    pub fn resize(&mut self, device: &wgpu::Device, width: u32, height: u32) {
+        let format = match self.format {
+            Some(format) => format,
+            None => return,
+        };
+        let samples = self.manifest.project.msaa;
+
+        if self.targets.as_ref().is_some_and(|targets| {
+            (
+                targets.width,
+                targets.height,
+                targets.format,
+                targets.samples,
+            ) == (width, height, format, samples)
+        }) {
+            return;
+        }
+
+        let create_view = |label, format| {
+            let texture = device.create_texture(&wgpu::TextureDescriptor {
+                label: Some(label),
+                size: wgpu::Extent3d {
+                    width,
+                    height,
+                    depth_or_array_layers: 1,
+                },
+                mip_level_count: 1,
+                sample_count: samples,
+                dimension: wgpu::TextureDimension::D2,
+                format,
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+            });
+
+            texture.create_view(&Default::default())
+        };
+
+        self.targets = Some(RenderTargets {
+            width,
+            height,
+            format,
+            samples,
+            depth: create_view("kiln-depth-texture", DEPTH_FORMAT),
+            color: (samples > 1).then(|| create_view("kiln-msaa-texture", format)),
+        });
+    }
+
+    /// Builds or hot reloads shaders and buffers, targeting `format`.
+    pub fn update(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+    ) -> Result<()> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let result = self.update_shaders(device, queue, format);
+        let error = future::block_on(device.pop_error_scope());
+
+        result?;
+
+        if let Some(error) = error {
+            self.invalid = true;
+            return Err(Error::Wgpu(error));
+        }
+
+        Ok(())
+    }
+
+    fn update_shaders(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        format: wgpu::TextureFormat,
+    ) -> Result<()> {
+        if self.format != Some(format) {
+            self.format = Some(format);
+            self.rebuild = true;
+        }
+
+        if self.update_processor()? {
+            self.rebuild = true;
+        }
+
+        let samples = self.manifest.project.msaa;
+        if !matches!(samples, 1 | 4) {
+            return Err(Error::UnsupportedSampleCount(samples));
+        }
+
+        let storage_updated = self
+            .storage
+            .as_ref()
+            .is_none_or(|storage| storage.manifest != self.manifest.buffers);
+        if storage_updated {
+            self.storage = Some(StorageBuffers::new(
+                device,
+                queue,
+                &self.path,
+                &self.manifest.buffers,
+            )?);
+        }
+        let storage = self.storage.as_ref().unwrap();
+
+        // only the file affects the decoded texture, volume is left to playback
+        let file = |audio: Option<&ManifestAudio>| audio.map(|audio| audio.file.clone());
+        let audio_updated = self.audio.as_ref().is_none_or(|audio| {
+            file(audio.manifest.as_ref()) != file(self.manifest.audio.as_ref())
+        });
+        if audio_updated {
+            self.audio = Some(Audio::new(
+                device,
+                &self.path,
+                self.manifest.audio.as_ref(),
+            )?);
+        }
+        let audio = self.audio.as_mut().unwrap();
+        audio.manifest = self.manifest.audio.clone();
+        let audio = &*audio;
+
+        // only shaders whose pipeline changed are rebuilt, the rest keep theirs
+        let rebuild = self.rebuild || storage_updated || audio_updated;
+        let mut built = LinkedHashMap::new();
+
+        for (name, shader) in self.manifest.shaders.iter() {
+            let kind = ShaderKind::from_manifest(&self.path, name, shader);
+            let reuse = !rebuild
+                && self.shaders.get(name).is_some_and(|shader| {
+                    shader.format == format
+                        && shader.samples == samples
+                        && shader.kind.same_pipeline(&kind)
+                });
+
+            if !reuse {
+                let shader = Shader::new(
+                    device,
+                    &mut self.processor,
+                    storage,
+                    audio,
+                    kind,
+                    format,
+                    samples,
+                )?;
+                built.insert(name.clone(), shader);
+            }
+        }
+
+        // a validation error may clear up once the shaders causing it are replaced
+        if !built.is_empty() || self.shaders.len() != self.manifest.shaders.len() {
+            self.invalid = false;
+        }
+
+        let mut previous = std::mem::take(&mut self.shaders);
+        for (name, manifest) in self.manifest.shaders.iter() {
+            let shader = match built.remove(name) {
+                Some(shader) => shader,
+                None => {
+                    let mut shader = previous.remove(name).unwrap();
+                    shader.kind = ShaderKind::from_manifest(&self.path, name, manifest);
+                    shader
+                }
+            };
+
+            self.shaders.insert(name.clone(), shader);
+        }
+
+        self.rebuild = false;
+
+        for (_, shader) in self.shaders.iter_mut() {
+            if shader.update(device, &mut self.processor)? {
+                self.invalid = false;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Encodes compute and render passes for one frame into `encoder`.
+    pub fn draw(
+        &self,
+        queue: &wgpu::Queue,
+        encoder: &mut wgpu::CommandEncoder,
+        target_view: &wgpu::TextureView,
+        uniforms: &ShaderUniforms,
+        mut profiler: Option<&mut Profiler>,
+    ) {
+        self.write_uniforms(queue, uniforms);
+        self.dispatch(encoder, profiler.as_deref_mut());
+        self.render(encoder, target_view, profiler);
+    }
+
+    /// Writes `uniforms` and the audio spectrum at `uniforms.time`.
+    pub fn write_uniforms(&self, queue: &wgpu::Queue, uniforms: &ShaderUniforms) {
+        for shader in self.shaders.values() {
+            shader.write_uniforms(queue, uniforms);
+        }
+
+        if let Some(ref audio) = self.audio {
+            audio.write(queue, uniforms.time);
+        }
+    }
+
+    /// Encodes the compute passes, advancing any simulation state by one frame.
+    pub fn dispatch(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        mut profiler: Option<&mut Profiler>,
+    ) {
+        let (storage, audio) = match (&self.storage, &self.audio) {
+            (Some(storage), Some(audio)) => (storage, audio),
+            _ => return,
+        };
+
+        for (name, shader) in self.shaders.iter() {
+            if let (ShaderPipeline::Compute(pipeline), ShaderKind::Compute { workgroups, .. }) =
+                (&shader.pipeline, &shader.kind)
+            {
+                if let Some(ref mut profiler) = profiler {
+                    profiler.begin(encoder, name);
+                }
+
+                let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                    label: Some("kiln-compute-pass"),
+                });
+
+                compute_pass.set_pipeline(pipeline);
+                compute_pass.set_bind_group(0, &shader.uniforms_group, &[]);
+                compute_pass.set_bind_group(1, &storage.compute_group, &[]);
+                compute_pass.set_bind_group(2, &audio.group, &[]);
+                compute_pass.dispatch_workgroups(workgroups[0], workgroups[1], workgroups[2]);
+
+                drop(compute_pass);
+
+                if let Some(ref mut profiler) = profiler {
+                    profiler.end(encoder);
+                }
+            }
+        }
+    }
+
+    /// Encodes the render passes, drawing into `target_view`, which must match
+    /// the size last passed to [`Project::resize`].
+    pub fn render(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target_view: &wgpu::TextureView,
+        mut profiler: Option<&mut Profiler>,
+    ) {
+        let (storage, audio, targets) = match (&self.storage, &self.audio, &self.targets) {
+            (Some(storage), Some(audio), Some(targets)) => (storage, audio, targets),
+            _ => return,
+        };
+
+        // with msaa every pass draws into the multisampled target and resolves into the output
+        let (color_view, resolve_target) = match targets.color {
+            Some(ref color) => (color, Some(target_view)),
+            None => (target_view, None),
+        };
+
+        let clear_color = self.manifest.project.clear_color();
+        let mut cleared = false;
+
+        for (name, shader) in self.shaders.iter() {
+            let (pipeline, load, vertices, instances) = match (&shader.pipeline, &shader.kind) {
+                (
+                    ShaderPipeline::Render(pipeline),
+                    ShaderKind::Render {
+                        load,
+                        vertices,
+                        instances,
+                        ..
+                    },
+                ) => (pipeline, load, *vertices, instances),
+                _ => continue,
+            };
+
+            let instances = match instances {
+                ManifestInstances::Count(count) => *count,
+                ManifestInstances::Buffer(name) => {
+                    storage.lengths.get(name).copied().unwrap_or_default() as u32
+                }
+            };
+
+            let load = match load {
+                Some(ManifestLoad::Clear) => wgpu::LoadOp::Clear(clear_color),
+                Some(ManifestLoad::Load) => wgpu::LoadOp::Load,
+                None if cleared => wgpu::LoadOp::Load,
+                None => wgpu::LoadOp::Clear(clear_color),
+            };
+            let depth_load = match cleared {
+                true => wgpu::LoadOp::Load,
+                false => wgpu::LoadOp::Clear(1.0),
+            };
+            cleared = true;
+
+            if let Some(ref mut profiler) = profiler {
+                profiler.begin(encoder, name);
+            }
+
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("kiln-render-pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: color_view,
+                    resolve_target,
+                    ops: wgpu::Operations { load, store: true },
+                })],
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &targets.depth,
+                    depth_ops: Some(wgpu::Operations {
+                        load: depth_load,
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+            });
+
+            render_pass.set_pipeline(pipeline);
+            render_pass.set_bind_group(0, &shader.uniforms_group, &[]);
+            render_pass.set_bind_group(1, &storage.render_group, &[]);
+            render_pass.set_bind_group(2, &audio.group, &[]);
+
+            match shader.mesh {
+                Some(ref mesh) => {
+                    render_pass.set_vertex_buffer(0, mesh.vertex_buffer.slice(..));
+                    render_pass
+                        .set_index_buffer(mesh.index_buffer.slice(..), wgpu::IndexFormat::Uint32);
+                    render_pass.draw_indexed(0..mesh.index_count, 0, 0..instances);
+                }
+                None => render_pass.draw(0..vertices, 0..instances),
+            }
+
+            drop(render_pass);
+
+            if let Some(ref mut profiler) = profiler {
+                profiler.end(encoder);
+            }
+        }
+
+        if !cleared {
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("kiln-clear-pass"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: target_view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(clear_color),
+                        store: true,
+                    },
+                })],
+                depth_stencil_attachment: None,
+            });
+        }
+    }
+}
@@ -1,4 +1,37 @@
-use futures_lite::future;
+use crate::{device::DeviceOptions, error::Result};
+
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    /// Prefer an `Rgba16Float` surface for extended range output.
+    pub hdr: bool,
+    pub present_mode: wgpu::PresentMode,
+    pub device: DeviceOptions,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            hdr: false,
+            present_mode: wgpu::PresentMode::Fifo,
+            device: DeviceOptions::default(),
+        }
+    }
+}
+
+/// Picks the surface format, `Rgba16Float` with `hdr` if the surface supports
+/// it and an srgb format otherwise.
+pub fn surface_format(formats: &[wgpu::TextureFormat], hdr: bool) -> wgpu::TextureFormat {
+    if hdr && formats.contains(&wgpu::TextureFormat::Rgba16Float) {
+        return wgpu::TextureFormat::Rgba16Float;
+    }
+
+    formats
+        .iter()
+        .copied()
+        .find(|format| format.describe().srgb)
+        .or_else(|| formats.first().copied())
+        .unwrap_or(wgpu::TextureFormat::Bgra8UnormSrgb)
+}
 
 pub struct Renderer {
     pub device: wgpu::Device,
@@ -10,50 +43,41 @@ pub struct Renderer {
 }
 
 impl Renderer {
-    pub unsafe fn new(window: winit::window::Window) -> Self {
+    /// Creates a device and a surface presenting to `window`.
+    ///
+    /// # Safety
+    ///
+    /// The surface is created from the raw handle of `window`, which must be
+    /// valid; the renderer keeps the window alive for as long as the surface.
+    pub unsafe fn new(window: winit::window::Window, options: &RenderOptions) -> Result<Self> {
         let size = window.inner_size();
 
-        let instance = wgpu::Instance::new(wgpu::Backends::all());
+        let instance = wgpu::Instance::new(options.device.backends);
 
         let surface = unsafe { instance.create_surface(&window) };
 
-        let adapter_fut = instance.request_adapter(&wgpu::RequestAdapterOptions {
-            power_preference: wgpu::PowerPreference::HighPerformance,
-            force_fallback_adapter: false,
-            compatible_surface: Some(&surface),
-        });
-
-        let adapter = future::block_on(adapter_fut).unwrap();
+        let (adapter, device, queue) = options.device.request_device(&instance, Some(&surface))?;
 
-        let device_fut = adapter.request_device(
-            &wgpu::DeviceDescriptor {
-                label: Some("kiln-device"),
-                features: wgpu::Features::empty(),
-                limits: wgpu::Limits::default(),
-            },
-            None,
-        );
-
-        let (device, queue) = future::block_on(device_fut).unwrap();
+        let formats = surface.get_supported_formats(&adapter);
 
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-            format: wgpu::TextureFormat::Bgra8UnormSrgb,
+            format: surface_format(&formats, options.hdr),
             width: size.width,
             height: size.height,
-            present_mode: wgpu::PresentMode::Fifo,
+            present_mode: options.present_mode,
         };
 
         surface.configure(&device, &config);
 
-        Self {
+        Ok(Self {
             device,
             queue,
             config,
             surface,
             window,
             should_configure: false,
-        }
+        })
     }
 
     pub fn configure(&mut self) {
@@ -4,50 +4,299 @@ use std::{
 };
 
 use bytemuck::{Pod, Zeroable};
+use glam::Mat4;
+use thiserror::Error;
 
 use wgpu::ShaderModule;
 
 use crate::{
+    audio::Audio,
     error::{Error, Result},
+    manifest::{ManifestBlend, ManifestInstances, ManifestLoad, ManifestMesh, ManifestShader},
+    mesh::{Mesh, MeshData, Vertex},
     shader_processor::ShaderProcessor,
+    storage::{StorageBufferError, StorageBuffers},
 };
 
+pub const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Default, Pod, Zeroable)]
 pub struct ShaderUniforms {
     pub view: [[f32; 4]; 4],
     pub aspect: f32,
     pub time: f32,
+    pub resolution: [f32; 2],
+    pub model: [[f32; 4]; 4],
+    pub projection: [[f32; 4]; 4],
+    pub view_projection: [[f32; 4]; 4],
+}
+
+impl ShaderUniforms {
+    /// Uniforms of a `width` by `height` frame seen through a camera, with
+    /// `view` transforming from camera to world space.
+    pub fn new(width: u32, height: u32, time: f32, view: Mat4, projection: Mat4) -> Self {
+        Self {
+            view: view.to_cols_array_2d(),
+            aspect: width as f32 / height as f32,
+            time,
+            resolution: [width as f32, height as f32],
+            model: Mat4::IDENTITY.to_cols_array_2d(),
+            projection: projection.to_cols_array_2d(),
+            view_projection: (projection * view.inverse()).to_cols_array_2d(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ShaderKind {
+    Render {
+        vertex: Option<PathBuf>,
+        vertex_entry: String,
+        fragment: PathBuf,
+        fragment_entry: String,
+        blend: ManifestBlend,
+        load: Option<ManifestLoad>,
+        mesh: Option<ManifestMesh>,
+        subdivisions: Option<u32>,
+        vertices: u32,
+        instances: ManifestInstances,
+        model: Mat4,
+    },
+    Compute {
+        compute: PathBuf,
+        entry: String,
+        workgroups: [u32; 3],
+    },
+}
+
+impl ShaderKind {
+    pub fn from_manifest(root: &Path, name: &str, shader: &ManifestShader) -> Self {
+        if let Some(ref compute) = shader.compute {
+            ShaderKind::Compute {
+                compute: root.join(compute),
+                entry: shader.entry.clone().unwrap_or_else(|| String::from("comp")),
+                workgroups: shader.workgroups,
+            }
+        } else {
+            let fragment = if let Some(ref fragment) = shader.fragment {
+                fragment.clone()
+            } else {
+                PathBuf::from(format!("{}.wgsl", name))
+            };
+
+            ShaderKind::Render {
+                vertex: shader.vertex.as_ref().map(|vertex| root.join(vertex)),
+                vertex_entry: shader
+                    .vertex_entry
+                    .clone()
+                    .unwrap_or_else(|| String::from("vert")),
+                fragment: root.join(fragment),
+                fragment_entry: shader.entry.clone().unwrap_or_else(|| String::from("frag")),
+                blend: shader.blend,
+                load: shader.load,
+                mesh: shader.mesh.as_ref().map(|mesh| match mesh {
+                    ManifestMesh::File(path) => ManifestMesh::File(root.join(path)),
+                    mesh => mesh.clone(),
+                }),
+                subdivisions: shader.subdivisions,
+                vertices: shader.vertices,
+                instances: shader.instances.clone(),
+                model: shader.transform.matrix(),
+            }
+        }
+    }
+
+    /// Whether a shader built from `self` can be reused for `other`, which may
+    /// only differ in its transform.
+    pub fn same_pipeline(&self, other: &Self) -> bool {
+        let mut other = other.clone();
+
+        if let (
+            ShaderKind::Render { model, .. },
+            ShaderKind::Render {
+                model: other_model, ..
+            },
+        ) = (self, &mut other)
+        {
+            *other_model = *model;
+        }
+
+        *self == other
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            ShaderKind::Render { fragment, .. } => fragment,
+            ShaderKind::Compute { compute, .. } => compute,
+        }
+    }
+
+    /// Entry points this shader needs, a `None` path being a built-in vertex shader.
+    pub fn entries(&self) -> Vec<ShaderEntry<'_>> {
+        match self {
+            ShaderKind::Render {
+                vertex,
+                vertex_entry,
+                fragment,
+                fragment_entry,
+                mesh,
+                ..
+            } => vec![
+                ShaderEntry {
+                    path: vertex.as_deref(),
+                    builtin: match mesh {
+                        Some(_) => "kiln/default_mesh_vertex",
+                        None => "kiln/default_vertex",
+                    },
+                    name: vertex_entry,
+                    stage: naga::ShaderStage::Vertex,
+                },
+                ShaderEntry {
+                    path: Some(fragment),
+                    builtin: "",
+                    name: fragment_entry,
+                    stage: naga::ShaderStage::Fragment,
+                },
+            ],
+            ShaderKind::Compute { compute, entry, .. } => vec![ShaderEntry {
+                path: Some(compute),
+                builtin: "",
+                name: entry,
+                stage: naga::ShaderStage::Compute,
+            }],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ShaderEntry<'a> {
+    pub path: Option<&'a Path>,
+    /// Global shader used when there's no `path`.
+    pub builtin: &'static str,
+    pub name: &'a str,
+    pub stage: naga::ShaderStage,
+}
+
+impl<'a> ShaderEntry<'a> {
+    pub fn source(&self, processor: &mut ShaderProcessor) -> Result<String> {
+        match self.path {
+            Some(path) => processor.process(path),
+            None => processor.process_global(self.builtin),
+        }
+    }
+
+    pub fn parse(&self, source: &str) -> std::result::Result<naga::Module, ShaderError> {
+        naga::front::wgsl::parse_str(source).map_err(|err| ShaderError::Parse {
+            path: self.display_path(),
+            message: err.emit_to_string(source),
+        })
+    }
+
+    pub fn verify(&self, module: &naga::Module) -> std::result::Result<(), ShaderError> {
+        let found = module
+            .entry_points
+            .iter()
+            .any(|entry| entry.name == self.name && entry.stage == self.stage);
+
+        if found {
+            return Ok(());
+        }
+
+        let available = module
+            .entry_points
+            .iter()
+            .filter(|entry| entry.stage == self.stage)
+            .map(|entry| entry.name.as_str())
+            .collect::<Vec<_>>();
+
+        Err(ShaderError::MissingEntry {
+            path: self.display_path(),
+            name: self.name.to_string(),
+            stage: self.stage,
+            available: available.join(", "),
+        })
+    }
+
+    pub fn display_path(&self) -> String {
+        match self.path {
+            Some(path) => path.display().to_string(),
+            None => format!("<{}>", self.builtin),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ShaderPipeline {
+    Render(wgpu::RenderPipeline),
+    Compute(wgpu::ComputePipeline),
 }
 
+/// A manifest shader entry built into a pipeline, with the bind groups and
+/// mesh it draws with.
 #[derive(Debug)]
 pub struct Shader {
-    pub vertex_path: Option<PathBuf>,
-    pub fragment_path: PathBuf,
+    pub kind: ShaderKind,
     pub last_modified: SystemTime,
     pub uniforms_group: wgpu::BindGroup,
     pub uniforms_buffer: wgpu::Buffer,
     pub pipeline_layout: wgpu::PipelineLayout,
-    pub pipeline: wgpu::RenderPipeline,
+    pub pipeline: ShaderPipeline,
+    pub mesh: Option<Mesh>,
+    pub format: wgpu::TextureFormat,
+    pub samples: u32,
 }
 
 impl Shader {
     pub fn new(
         device: &wgpu::Device,
         processor: &mut ShaderProcessor,
-        vertex_path: Option<PathBuf>,
-        fragment_path: PathBuf,
+        storage: &StorageBuffers,
+        audio: &Audio,
+        kind: ShaderKind,
+        format: wgpu::TextureFormat,
+        samples: u32,
     ) -> Result<Self> {
-        if !fragment_path.exists() {
-            return Err(Error::InvalidPath(fragment_path));
+        let path = kind.path();
+        if !path.exists() {
+            return Err(Error::InvalidPath(path.to_path_buf()));
         }
-        let meta = fragment_path.metadata()?;
+        let meta = path.metadata()?;
+
+        if let ShaderKind::Render {
+            instances: ManifestInstances::Buffer(ref name),
+            ..
+        } = kind
+        {
+            if !storage.lengths.contains_key(name) {
+                return Err(StorageBufferError::UnknownBuffer(name.clone()).into());
+            }
+        }
+
+        let mesh = match kind {
+            ShaderKind::Render {
+                mesh: Some(ManifestMesh::File(ref path)),
+                ..
+            } => {
+                if !path.exists() {
+                    return Err(Error::InvalidPath(path.clone()));
+                }
+
+                Some(Mesh::new(device, &MeshData::load(path)?))
+            }
+            ShaderKind::Render {
+                mesh: Some(ManifestMesh::Shape(shape)),
+                subdivisions,
+                ..
+            } => Some(Mesh::new(device, &MeshData::shape(shape, subdivisions))),
+            _ => None,
+        };
 
         let uniforms_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             label: Some("kiln-uniforms-layout"),
             entries: &[wgpu::BindGroupLayoutEntry {
                 binding: 0,
-                visibility: wgpu::ShaderStages::FRAGMENT,
+                visibility: wgpu::ShaderStages::VERTEX_FRAGMENT | wgpu::ShaderStages::COMPUTE,
                 ty: wgpu::BindingType::Buffer {
                     ty: wgpu::BufferBindingType::Uniform,
                     min_binding_size: None,
@@ -57,15 +306,20 @@ impl Shader {
             }],
         });
 
+        let storage_layout = match kind {
+            ShaderKind::Render { .. } => &storage.render_layout,
+            ShaderKind::Compute { .. } => &storage.compute_layout,
+        };
+
         let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("kiln-pipeline-layout"),
-            bind_group_layouts: &[&uniforms_layout],
+            bind_group_layouts: &[&uniforms_layout, storage_layout, &audio.layout],
             push_constant_ranges: &[],
         });
 
         let uniforms_buffer = device.create_buffer(&wgpu::BufferDescriptor {
             label: Some("kiln-uniforms-buffer"),
-            size: 256,
+            size: std::mem::size_of::<ShaderUniforms>() as u64,
             mapped_at_creation: false,
             usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
         });
@@ -79,19 +333,19 @@ impl Shader {
             }],
         });
 
-        let (vertex_module, fragment_module) =
-            Self::load_shaders(device, processor, vertex_path.as_deref(), &fragment_path)?;
         let pipeline =
-            Self::create_pipeline(&vertex_module, &fragment_module, device, &pipeline_layout);
+            Self::load_pipeline(device, processor, &kind, &pipeline_layout, format, samples)?;
 
         Ok(Self {
-            vertex_path,
-            fragment_path,
+            kind,
             last_modified: meta.modified()?,
             uniforms_group,
             uniforms_buffer,
             pipeline_layout,
             pipeline,
+            mesh,
+            format,
+            samples,
         })
     }
 
@@ -100,25 +354,20 @@ impl Shader {
         device: &wgpu::Device,
         processor: &mut ShaderProcessor,
     ) -> Result<bool> {
-        let modified = self.fragment_path.metadata()?.modified()?;
+        let modified = self.kind.path().metadata()?.modified()?;
 
         if modified > self.last_modified {
             self.last_modified = modified;
 
             processor.invalidate_locals();
-            let (vertex_module, fragment_module) = Self::load_shaders(
+            self.pipeline = Self::load_pipeline(
                 device,
                 processor,
-                self.vertex_path.as_deref(),
-                &self.fragment_path,
-            )?;
-
-            self.pipeline = Self::create_pipeline(
-                &vertex_module,
-                &fragment_module,
-                device,
+                &self.kind,
                 &self.pipeline_layout,
-            );
+                self.format,
+                self.samples,
+            )?;
 
             Ok(true)
         } else {
@@ -127,64 +376,117 @@ impl Shader {
     }
 
     pub fn write_uniforms(&self, queue: &wgpu::Queue, uniforms: &ShaderUniforms) {
-        let bytes = bytemuck::bytes_of(uniforms);
-        queue.write_buffer(&self.uniforms_buffer, 0, bytes);
+        let mut uniforms = *uniforms;
+
+        if let ShaderKind::Render { model, .. } = self.kind {
+            uniforms.model = model.to_cols_array_2d();
+        }
+
+        queue.write_buffer(&self.uniforms_buffer, 0, bytemuck::bytes_of(&uniforms));
     }
 
-    fn load_shaders(
+    fn load_module(
         device: &wgpu::Device,
         processor: &mut ShaderProcessor,
-        vertex_path: Option<&Path>,
-        fragment_path: &Path,
-    ) -> Result<(ShaderModule, ShaderModule)> {
-        let fragment_source = processor.process(&fragment_path)?;
-        let fragment_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        entry: ShaderEntry<'_>,
+    ) -> Result<ShaderModule> {
+        let source = entry.source(processor)?;
+        entry.verify(&entry.parse(&source)?)?;
+
+        Ok(device.create_shader_module(wgpu::ShaderModuleDescriptor {
             label: Some("kiln-shader"),
-            source: wgpu::ShaderSource::Wgsl(fragment_source.into()),
-        });
+            source: wgpu::ShaderSource::Wgsl(source.into()),
+        }))
+    }
 
-        let vertex_module = if let Some(vertex_path) = vertex_path {
-            let vertex_source = processor.process(&vertex_path)?;
+    fn load_pipeline(
+        device: &wgpu::Device,
+        processor: &mut ShaderProcessor,
+        kind: &ShaderKind,
+        layout: &wgpu::PipelineLayout,
+        format: wgpu::TextureFormat,
+        samples: u32,
+    ) -> Result<ShaderPipeline> {
+        let entries = kind.entries();
 
-            device.create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: Some("kiln-shader"),
-                source: wgpu::ShaderSource::Wgsl(vertex_source.into()),
-            })
-        } else {
-            device.create_shader_module(wgpu::ShaderModuleDescriptor {
-                label: Some("kiln-shader"),
-                source: wgpu::ShaderSource::Wgsl(
-                    include_str!("include/default_vertex.wgsl").into(),
-                ),
-            })
-        };
+        match kind {
+            ShaderKind::Render {
+                vertex_entry,
+                fragment_entry,
+                blend,
+                mesh,
+                ..
+            } => {
+                let vertex_module = Self::load_module(device, processor, entries[0])?;
+                let fragment_module = Self::load_module(device, processor, entries[1])?;
 
-        Ok((vertex_module, fragment_module))
+                Ok(ShaderPipeline::Render(Self::create_pipeline(
+                    (&vertex_module, vertex_entry),
+                    (&fragment_module, fragment_entry),
+                    blend.state(),
+                    mesh.is_some(),
+                    (format, samples),
+                    device,
+                    layout,
+                )))
+            }
+            ShaderKind::Compute { entry, .. } => {
+                let compute_module = Self::load_module(device, processor, entries[0])?;
+
+                Ok(ShaderPipeline::Compute(device.create_compute_pipeline(
+                    &wgpu::ComputePipelineDescriptor {
+                        label: Some("kiln-compute-pipeline"),
+                        layout: Some(layout),
+                        module: &compute_module,
+                        entry_point: entry,
+                    },
+                )))
+            }
+        }
     }
 
     fn create_pipeline(
-        vertex_module: &wgpu::ShaderModule,
-        fragment_module: &wgpu::ShaderModule,
+        (vertex_module, vertex_entry): (&wgpu::ShaderModule, &str),
+        (fragment_module, fragment_entry): (&wgpu::ShaderModule, &str),
+        blend: Option<wgpu::BlendState>,
+        mesh: bool,
+        (format, samples): (wgpu::TextureFormat, u32),
         device: &wgpu::Device,
         layout: &wgpu::PipelineLayout,
     ) -> wgpu::RenderPipeline {
+        // fullscreen passes ignore the depth buffer, meshes test and write it
+        let (buffers, depth_compare): (&[_], _) = if mesh {
+            (&[Vertex::layout()], wgpu::CompareFunction::Less)
+        } else {
+            (&[], wgpu::CompareFunction::Always)
+        };
+
         device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("kiln-shader-pipeline"),
             layout: Some(layout),
             vertex: wgpu::VertexState {
                 module: vertex_module,
-                entry_point: "vert",
-                buffers: &[],
+                entry_point: vertex_entry,
+                buffers,
             },
             primitive: Default::default(),
-            depth_stencil: None,
-            multisample: Default::default(),
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: mesh,
+                depth_compare,
+                stencil: Default::default(),
+                bias: Default::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: samples,
+                ..Default::default()
+            },
             fragment: Some(wgpu::FragmentState {
                 module: fragment_module,
-                entry_point: "frag",
+                entry_point: fragment_entry,
                 targets: &[Some(wgpu::ColorTargetState {
-                    format: wgpu::TextureFormat::Bgra8UnormSrgb,
-                    blend: None,
+                    format,
+                    blend,
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
@@ -192,3 +494,39 @@ impl Shader {
         })
     }
 }
+
+#[derive(Debug, Error)]
+pub enum ShaderError {
+    #[error("failed to parse '{path}':\n{message}")]
+    Parse { path: String, message: String },
+    #[error("no {stage:?} entry point '{name}' in '{path}', available: [{available}]")]
+    MissingEntry {
+        path: String,
+        name: String,
+        stage: naga::ShaderStage,
+        available: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_pipeline() {
+        let kind = |source: &str| {
+            let shader = toml::from_str::<ManifestShader>(source).unwrap();
+            ShaderKind::from_manifest(Path::new(""), "a", &shader)
+        };
+
+        let shader = kind("fragment = \"a.wgsl\"\n");
+        let moved = kind("fragment = \"a.wgsl\"\ntransform = { translation = [0.0, 1.0, 0.0] }\n");
+        let blended = kind("fragment = \"a.wgsl\"\nblend = \"alpha\"\n");
+        let entry = kind("fragment = \"a.wgsl\"\nentry = \"main\"\n");
+
+        assert!(shader != moved);
+        assert!(shader.same_pipeline(&moved));
+        assert!(!shader.same_pipeline(&blended));
+        assert!(!shader.same_pipeline(&entry));
+    }
+}
@@ -1,6 +1,6 @@
 use std::{
     borrow::Cow,
-    collections::{HashMap, HashSet, VecDeque},
+    collections::{HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
     time::SystemTime,
@@ -49,7 +49,7 @@ impl<'a> ShaderIncludePath<'a> {
                         let full_path = path
                             .expect("path not supplied for include path {internal error}")
                             .parent()
-                            .expect("all files should have a parent")
+                            .unwrap_or_else(|| Path::new(""))
                             .join(Path::new(path_source));
                         Ok(Self::Local(full_path.into()))
                     }
@@ -75,7 +75,8 @@ impl<'a> ShaderIncludePath<'a> {
 
 #[derive(Clone, Debug)]
 pub struct ShaderInclude {
-    pub includes: HashSet<ShaderIncludePath<'static>>,
+    /// Included paths in the order they appear, so output is deterministic.
+    pub includes: Vec<ShaderIncludePath<'static>>,
     pub source: Cow<'static, str>,
 }
 
@@ -83,7 +84,7 @@ impl ShaderInclude {
     pub const IDENTIFIER: &'static str = "#include";
 
     pub fn parse(mut source: &str, path: Option<&Path>) -> Result<Self, ShaderProcessorError> {
-        let mut includes = HashSet::new();
+        let mut includes = Vec::new();
 
         let mut cleaned_source = String::new();
         while let Some(index) = source.find(Self::IDENTIFIER) {
@@ -112,7 +113,10 @@ impl ShaderInclude {
             let end = source.find('\n').unwrap_or(source.len());
 
             let path_source = source[..end].trim();
-            includes.insert(ShaderIncludePath::parse(path_source, path)?);
+            let include_path = ShaderIncludePath::parse(path_source, path)?;
+            if !includes.contains(&include_path) {
+                includes.push(include_path);
+            }
 
             source = &source[end..];
         }
@@ -129,16 +133,35 @@ impl ShaderInclude {
 impl From<&'static str> for ShaderInclude {
     fn from(source: &'static str) -> Self {
         Self {
-            includes: HashSet::new(),
+            includes: Vec::new(),
             source: source.into(),
         }
     }
 }
 
+/// Resolves the `#include`s of wgsl shaders into a single source.
+///
+/// Local includes, `#include "noise.wgsl"`, are relative to the including
+/// file. Globals, `#include <kiln/camera>`, are built in, exported by a
+/// dependency or found in an include directory.
+///
+/// ```no_run
+/// let mut processor = kiln::ShaderProcessor::new();
+/// processor.set_include_dirs(vec!["shaders/lib".into()]);
+///
+/// let source = processor.process("shaders/main.wgsl".as_ref())?;
+/// # Ok::<(), kiln::Error>(())
+/// ```
 #[derive(Clone, Debug, Default)]
 pub struct ShaderProcessor {
     includes: HashMap<ShaderIncludePath<'static>, ShaderInclude>,
     modified: HashMap<ShaderIncludePath<'static>, SystemTime>,
+    /// Directories searched for globals that aren't built in, in order.
+    include_dirs: Vec<PathBuf>,
+    /// Files exported by dependencies, by global name.
+    exports: HashMap<String, PathBuf>,
+    /// Globals found in `exports` or `include_dirs`, included like local files.
+    resolved: HashMap<String, PathBuf>,
 }
 
 impl ShaderProcessor {
@@ -158,12 +181,9 @@ impl ShaderProcessor {
                 .insert(include_path.clone(), shader_include.clone());
 
             for _include_path in shader_include.includes.iter() {
-                match self.verify_include_path(_include_path) {
-                    Err(err) => {
-                        self.includes.remove(&include_path);
-                        return Err(err);
-                    }
-                    _ => {}
+                if let Err(err) = self.verify_include_path(_include_path) {
+                    self.includes.remove(&include_path);
+                    return Err(err);
                 }
             }
 
@@ -177,7 +197,15 @@ impl ShaderProcessor {
     fn verify_include_path(&mut self, include_path: &ShaderIncludePath<'_>) -> Result<(), Error> {
         match include_path {
             ShaderIncludePath::Global(global_path) if !self.includes.contains_key(include_path) => {
-                return Err(ShaderProcessorError::InvalidGlobal(global_path.clone()).into());
+                let path = match self.find_global(global_path) {
+                    Some(path) => path,
+                    None => {
+                        return Err(ShaderProcessorError::InvalidGlobal(global_path.clone()).into())
+                    }
+                };
+
+                self.verify_local(&path)?;
+                self.resolved.insert(global_path.clone(), path);
             }
             ShaderIncludePath::Local(local_path) => {
                 self.verify_local(local_path)?;
@@ -188,13 +216,50 @@ impl ShaderProcessor {
         Ok(())
     }
 
+    /// Looks up `name` in the dependency exports, then the include directories
+    /// as `name` or `name.wgsl`.
+    fn find_global(&self, name: &str) -> Option<PathBuf> {
+        if let Some(path) = self.exports.get(name) {
+            return fs::canonicalize(path).ok();
+        }
+
+        self.include_dirs.iter().find_map(|dir| {
+            let path = dir.join(name);
+            let path = match path.is_file() {
+                true => path,
+                false => dir.join(format!("{}.wgsl", name)),
+            };
+
+            fs::canonicalize(path).ok()
+        })
+    }
+
+    /// The include `include_path` refers to, globals found in the include
+    /// directories being local files.
+    fn resolve<'a>(&self, include_path: &ShaderIncludePath<'a>) -> ShaderIncludePath<'a> {
+        match include_path {
+            ShaderIncludePath::Global(name) if !self.includes.contains_key(include_path) => {
+                match self.resolved.get(name) {
+                    Some(path) => ShaderIncludePath::Local(path.clone().into()),
+                    None => include_path.clone(),
+                }
+            }
+            _ => include_path.clone(),
+        }
+    }
+
+    /// A processor without any globals.
     pub fn empty() -> Self {
         Self {
             includes: HashMap::new(),
             modified: HashMap::new(),
+            include_dirs: Vec::new(),
+            exports: HashMap::new(),
+            resolved: HashMap::new(),
         }
     }
 
+    /// A processor with kiln's built in globals, eg. `kiln/uniforms`.
     pub fn new() -> Self {
         let mut this = Self::empty();
         this.insert_global("kiln/uniforms", include_str!("include/uniforms.wgsl"), None)
@@ -207,12 +272,49 @@ impl ShaderProcessor {
             .unwrap();
         this.insert_global("kiln/post", include_str!("include/post.wgsl"), None)
             .unwrap();
+        this.insert_global("kiln/audio", include_str!("include/audio.wgsl"), None)
+            .unwrap();
+        this.insert_global("kiln/mesh", include_str!("include/mesh.wgsl"), None)
+            .unwrap();
+        let default_vertex = include_str!("include/default_vertex.wgsl");
+        this.insert_global("kiln/default_vertex", default_vertex, None)
+            .unwrap();
+        let default_mesh_vertex = include_str!("include/default_mesh_vertex.wgsl");
+        this.insert_global("kiln/default_mesh_vertex", default_mesh_vertex, None)
+            .unwrap();
 
         this
     }
 
     pub fn invalidate_locals(&mut self) {
         self.includes.retain(|k, _| !k.is_local());
+        self.resolved.clear();
+    }
+
+    /// Sets the directories searched for `#include <...>` globals that aren't
+    /// built in, returning whether they changed.
+    pub fn set_include_dirs(&mut self, include_dirs: Vec<PathBuf>) -> bool {
+        if self.include_dirs == include_dirs {
+            return false;
+        }
+
+        self.include_dirs = include_dirs;
+        self.invalidate_locals();
+
+        true
+    }
+
+    /// Sets the files dependencies export as globals, eg. `noise/fbm`,
+    /// returning whether they changed.
+    pub fn set_exports(&mut self, exports: HashMap<String, PathBuf>) -> bool {
+        if self.exports == exports {
+            return false;
+        }
+
+        self.exports = exports;
+        self.invalidate_locals();
+
+        true
     }
 
     pub fn insert_global(
@@ -221,12 +323,12 @@ impl ShaderProcessor {
         source: &str,
         path: Option<&Path>,
     ) -> Result<(), Error> {
-        ShaderIncludePath::verify_path(&include_path)?;
+        ShaderIncludePath::verify_path(include_path)?;
         let include_path = ShaderIncludePath::Global(String::from(include_path));
         let shader_include = ShaderInclude::parse(source, path)?;
 
         for include_path in shader_include.includes.iter() {
-            self.verify_include_path(&include_path)?;
+            self.verify_include_path(include_path)?;
         }
 
         self.includes.insert(include_path, shader_include);
@@ -234,49 +336,68 @@ impl ShaderProcessor {
         Ok(())
     }
 
+    /// Processes the shader at `path`. Files are read once and cached until
+    /// [`invalidate_locals`](Self::invalidate_locals) is called.
     pub fn process(&mut self, path: &Path) -> Result<String, Error> {
         let path = fs::canonicalize(path)?;
         self.verify_local(&path)?;
-        let include_path = ShaderIncludePath::Local(Cow::Borrowed(&path));
 
-        let mut queue = VecDeque::new();
-        let mut included = HashSet::new();
-        let mut visited = HashSet::new();
-        queue.push_back(include_path);
+        self.process_include(ShaderIncludePath::Local(Cow::Borrowed(&path)))
+    }
 
-        let mut processed_shader = String::new();
+    /// Processes a built-in shader, eg. `kiln/default_mesh_vertex`.
+    pub fn process_global(&mut self, name: &str) -> Result<String, Error> {
+        let include_path = ShaderIncludePath::Global(String::from(name));
+        self.verify_include_path(&include_path)?;
 
-        while let Some(include_path) = queue.pop_front() {
-            if !visited.insert(include_path.clone()) {
-                return Err(ShaderProcessorError::CyclicInclude.into());
-            }
+        self.process_include(include_path)
+    }
 
-            let include = &self.includes[&include_path];
+    fn process_include(&self, include_path: ShaderIncludePath<'_>) -> Result<String, Error> {
+        let mut included = HashSet::new();
+        let mut visiting = Vec::new();
+        let mut processed_shader = String::new();
 
-            let mut dependencies_included = true;
+        self.append_include(
+            &include_path,
+            &mut included,
+            &mut visiting,
+            &mut processed_shader,
+        )?;
 
-            for _include_path in include.includes.iter() {
-                if !included.contains(_include_path) {
-                    if !queue.contains(_include_path) {
-                        queue.push_back(_include_path.clone());
-                    }
+        Ok(processed_shader)
+    }
 
-                    dependencies_included = false;
-                }
-            }
+    /// Appends `include_path` after everything it includes, each include only once.
+    fn append_include<'a>(
+        &self,
+        include_path: &ShaderIncludePath<'a>,
+        included: &mut HashSet<ShaderIncludePath<'a>>,
+        visiting: &mut Vec<ShaderIncludePath<'a>>,
+        processed_shader: &mut String,
+    ) -> Result<(), Error> {
+        let include_path = &self.resolve(include_path);
 
-            if !dependencies_included {
-                queue.push_back(include_path);
-                continue;
-            }
+        if included.contains(include_path) {
+            return Ok(());
+        }
+
+        if visiting.contains(include_path) {
+            return Err(ShaderProcessorError::CyclicInclude.into());
+        }
 
-            visited.clear();
+        let include = &self.includes[include_path];
 
-            processed_shader += &include.source;
-            included.insert(include_path);
+        visiting.push(include_path.clone());
+        for _include_path in include.includes.iter() {
+            self.append_include(_include_path, included, visiting, processed_shader)?;
         }
+        visiting.pop();
 
-        Ok(processed_shader)
+        *processed_shader += &include.source;
+        included.insert(include_path.clone());
+
+        Ok(())
     }
 }
 
@@ -319,4 +440,42 @@ struct Foo {
             "\n\n\nstruct Foo {\n    a: i32,\n    b: f32,\n}"
         );
     }
+
+    #[test]
+    fn nested_includes() {
+        let mut processor = ShaderProcessor::empty();
+        processor.insert_global("a", "a;", None).unwrap();
+        processor
+            .insert_global("b", "#include <a>\nb;", None)
+            .unwrap();
+        processor
+            .insert_global("c", "#include <b>\n#include <a>\nc;", None)
+            .unwrap();
+
+        let source = processor.process_global("c").unwrap();
+        let source: String = source.chars().filter(|ch| !ch.is_whitespace()).collect();
+        assert_eq!(source, "a;b;c;");
+    }
+
+    #[test]
+    fn include_dirs() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path().to_path_buf();
+        fs::create_dir_all(dir.join("noise")).unwrap();
+        fs::write(dir.join("noise/hash.wgsl"), "hash;").unwrap();
+        fs::write(dir.join("noise/fbm.wgsl"), "#include \"hash.wgsl\"\nfbm;").unwrap();
+
+        let mut processor = ShaderProcessor::empty();
+        processor.insert_global("a", "a;", None).unwrap();
+        assert!(processor.set_include_dirs(vec![dir.clone()]));
+        assert!(!processor.set_include_dirs(vec![dir.clone()]));
+
+        processor
+            .insert_global("main", "#include <noise/fbm>\n#include <a>\nmain;", None)
+            .unwrap();
+        let source = processor.process_global("main").unwrap();
+
+        let source: String = source.chars().filter(|ch| !ch.is_whitespace()).collect();
+        assert_eq!(source, "hash;fbm;a;main;");
+    }
 }
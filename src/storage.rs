@@ -0,0 +1,372 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use linked_hash_map::LinkedHashMap;
+use thiserror::Error;
+
+use crate::{error::Error, manifest::ManifestBuffer};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScalarType {
+    F32,
+    I32,
+    U32,
+}
+
+impl ScalarType {
+    fn parse(source: &str) -> Option<Self> {
+        match source {
+            "f32" => Some(Self::F32),
+            "i32" => Some(Self::I32),
+            "u32" => Some(Self::U32),
+            _ => None,
+        }
+    }
+
+    fn write(self, value: f64, bytes: &mut Vec<u8>) {
+        match self {
+            Self::F32 => bytes.extend_from_slice(&(value as f32).to_le_bytes()),
+            Self::I32 => bytes.extend_from_slice(&(value as i32).to_le_bytes()),
+            Self::U32 => bytes.extend_from_slice(&(value as u32).to_le_bytes()),
+        }
+    }
+}
+
+/// A wgsl element type, laid out as it would be in a storage `array<T>`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ElementType {
+    pub scalar: ScalarType,
+    pub columns: u32,
+    pub rows: u32,
+}
+
+impl ElementType {
+    pub fn parse(source: &str) -> Result<Self, StorageBufferError> {
+        let unknown = || StorageBufferError::UnknownType(source.to_string());
+        let source: String = source.chars().filter(|ch| !ch.is_whitespace()).collect();
+
+        if let Some(scalar) = ScalarType::parse(&source) {
+            return Ok(Self {
+                scalar,
+                columns: 1,
+                rows: 1,
+            });
+        }
+
+        let (shape, scalar) = source
+            .strip_suffix('>')
+            .and_then(|source| source.split_once('<'))
+            .ok_or_else(unknown)?;
+        let scalar = ScalarType::parse(scalar).ok_or_else(unknown)?;
+
+        let dimension = |source: &str| match source {
+            "2" => Some(2),
+            "3" => Some(3),
+            "4" => Some(4),
+            _ => None,
+        };
+
+        if let Some(rows) = shape.strip_prefix("vec") {
+            return Ok(Self {
+                scalar,
+                columns: 1,
+                rows: dimension(rows).ok_or_else(unknown)?,
+            });
+        }
+
+        let (columns, rows) = shape
+            .strip_prefix("mat")
+            .and_then(|shape| shape.split_once('x'))
+            .ok_or_else(unknown)?;
+
+        if scalar != ScalarType::F32 {
+            return Err(unknown());
+        }
+
+        Ok(Self {
+            scalar,
+            columns: dimension(columns).ok_or_else(unknown)?,
+            rows: dimension(rows).ok_or_else(unknown)?,
+        })
+    }
+
+    pub fn components(&self) -> u32 {
+        self.columns * self.rows
+    }
+
+    pub fn column_stride(&self) -> u64 {
+        match self.rows {
+            1 => 4,
+            2 => 8,
+            _ => 16,
+        }
+    }
+
+    pub fn stride(&self) -> u64 {
+        self.column_stride() * self.columns as u64
+    }
+
+    fn encode(&self, values: &[f64]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+
+        for element in values.chunks(self.components() as usize) {
+            for column in element.chunks(self.rows as usize) {
+                let start = bytes.len();
+
+                for &value in column {
+                    self.scalar.write(value, &mut bytes);
+                }
+
+                bytes.resize(start + self.column_stride() as usize, 0);
+            }
+        }
+
+        bytes
+    }
+}
+
+impl Default for ElementType {
+    fn default() -> Self {
+        Self {
+            scalar: ScalarType::F32,
+            columns: 1,
+            rows: 1,
+        }
+    }
+}
+
+fn flatten_json(
+    value: &serde_json::Value,
+    values: &mut Vec<f64>,
+) -> Result<(), StorageBufferError> {
+    match value {
+        serde_json::Value::Number(number) => values.push(number.as_f64().unwrap_or_default()),
+        serde_json::Value::Bool(value) => values.push(*value as u32 as f64),
+        serde_json::Value::Array(array) => {
+            for value in array {
+                flatten_json(value, values)?;
+            }
+        }
+        _ => return Err(StorageBufferError::BadJson(value.to_string())),
+    }
+
+    Ok(())
+}
+
+fn load_init(path: &Path, element: &ElementType) -> Result<Vec<u8>, Error> {
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+
+    if is_json {
+        let source = fs::read_to_string(path)?;
+        let value: serde_json::Value =
+            serde_json::from_str(&source).map_err(StorageBufferError::from)?;
+
+        let mut values = Vec::new();
+        flatten_json(&value, &mut values)?;
+
+        if values.len() % element.components() as usize != 0 {
+            return Err(StorageBufferError::PartialElement(path.to_path_buf()).into());
+        }
+
+        Ok(element.encode(&values))
+    } else {
+        Ok(fs::read(path)?)
+    }
+}
+
+const fn align_size(size: u64) -> u64 {
+    let align = wgpu::COPY_BUFFER_ALIGNMENT;
+    let size = size.div_ceil(align) * align;
+
+    if size == 0 {
+        align
+    } else {
+        size
+    }
+}
+
+/// Storage buffers declared in the manifest, kept alive across frames.
+///
+/// Buffers are bound to group 1 in declaration order, read-only to vertex and
+/// fragment stages and read-write to compute stages.
+#[derive(Debug)]
+pub struct StorageBuffers {
+    pub manifest: LinkedHashMap<String, ManifestBuffer>,
+    /// Number of elements in each buffer.
+    pub lengths: LinkedHashMap<String, u64>,
+    pub render_layout: wgpu::BindGroupLayout,
+    pub render_group: wgpu::BindGroup,
+    pub compute_layout: wgpu::BindGroupLayout,
+    pub compute_group: wgpu::BindGroup,
+}
+
+impl StorageBuffers {
+    pub fn new(
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        root: &Path,
+        manifest: &LinkedHashMap<String, ManifestBuffer>,
+    ) -> Result<Self, Error> {
+        let mut buffers = Vec::new();
+        let mut lengths = LinkedHashMap::new();
+
+        for (name, buffer) in manifest.iter() {
+            let element = match buffer.ty {
+                Some(ref ty) => ElementType::parse(ty)?,
+                None => ElementType::default(),
+            };
+
+            let init = match buffer.init {
+                Some(ref init) => {
+                    let path = root.join(init);
+
+                    if !path.exists() {
+                        return Err(StorageBufferError::InvalidInit(path).into());
+                    }
+
+                    Some(load_init(&path, &element)?)
+                }
+                None => None,
+            };
+
+            let size = match (buffer.size, buffer.count, &init) {
+                (Some(size), _, _) => size,
+                (None, Some(count), _) => element.stride() * count,
+                (None, None, Some(init)) => init.len() as u64,
+                (None, None, None) => {
+                    return Err(StorageBufferError::MissingSize(name.clone()).into())
+                }
+            };
+            lengths.insert(name.clone(), size / element.stride());
+            let size = align_size(size);
+
+            let storage_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("kiln-storage-buffer"),
+                size,
+                mapped_at_creation: false,
+                usage: wgpu::BufferUsages::STORAGE
+                    | wgpu::BufferUsages::COPY_DST
+                    | wgpu::BufferUsages::COPY_SRC,
+            });
+
+            if let Some(mut init) = init {
+                if init.len() as u64 > size {
+                    return Err(StorageBufferError::InitTooLarge {
+                        name: name.clone(),
+                        size,
+                        len: init.len() as u64,
+                    }
+                    .into());
+                }
+
+                init.resize(align_size(init.len() as u64) as usize, 0);
+                queue.write_buffer(&storage_buffer, 0, &init);
+            }
+
+            buffers.push(storage_buffer);
+        }
+
+        let layout_entries = |visibility, read_only| {
+            (0..buffers.len() as u32)
+                .map(|binding| wgpu::BindGroupLayoutEntry {
+                    binding,
+                    visibility,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Storage { read_only },
+                        min_binding_size: None,
+                        has_dynamic_offset: false,
+                    },
+                    count: None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let render_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("kiln-storage-render-layout"),
+            entries: &layout_entries(
+                wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                true,
+            ),
+        });
+
+        let compute_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("kiln-storage-compute-layout"),
+            entries: &layout_entries(wgpu::ShaderStages::COMPUTE, false),
+        });
+
+        let group_entries = buffers
+            .iter()
+            .enumerate()
+            .map(|(binding, buffer)| wgpu::BindGroupEntry {
+                binding: binding as u32,
+                resource: buffer.as_entire_binding(),
+            })
+            .collect::<Vec<_>>();
+
+        let render_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("kiln-storage-render-group"),
+            layout: &render_layout,
+            entries: &group_entries,
+        });
+
+        let compute_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("kiln-storage-compute-group"),
+            layout: &compute_layout,
+            entries: &group_entries,
+        });
+
+        Ok(Self {
+            manifest: manifest.clone(),
+            lengths,
+            render_layout,
+            render_group,
+            compute_layout,
+            compute_group,
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum StorageBufferError {
+    #[error("buffer '{0}' needs a size, an element count or an init file")]
+    MissingSize(String),
+    #[error("no buffer named '{0}'")]
+    UnknownBuffer(String),
+    #[error("unknown element type '{0}'")]
+    UnknownType(String),
+    #[error("invalid init file '{0}'")]
+    InvalidInit(PathBuf),
+    #[error("buffer '{name}' is {size} bytes, but its init data is {len} bytes")]
+    InitTooLarge { name: String, size: u64, len: u64 },
+    #[error("init file '{0}' ends with a partial element")]
+    PartialElement(PathBuf),
+    #[error("expected a number or an array in init data, found '{0}'")]
+    BadJson(String),
+    #[error("invalid json: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn element_layout() {
+        let vec3 = ElementType::parse("vec3<f32>").unwrap();
+        assert_eq!(vec3.stride(), 16);
+
+        let mat = ElementType::parse("mat4x3<f32>").unwrap();
+        assert_eq!(mat.stride(), 64);
+
+        let bytes = vec3.encode(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(bytes.len(), 32);
+        assert_eq!(&bytes[16..20], &4.0f32.to_le_bytes());
+
+        assert!(ElementType::parse("vec5<f32>").is_err());
+        assert!(ElementType::parse("mat2x2<u32>").is_err());
+    }
+}
@@ -0,0 +1,39 @@
+@group(0) @binding(0)
+var source: texture_2d<f32>;
+@group(0) @binding(1)
+var source_sampler: sampler;
+
+struct VertexOutput {
+	@builtin(position) position: vec4<f32>,
+	@location(0) uv: vec2<f32>,
+};
+
+@vertex
+fn vert(@builtin(vertex_index) index: u32) -> VertexOutput {
+	let uv = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+
+	var out: VertexOutput;
+
+	out.position = vec4<f32>(uv * vec2<f32>(2.0, -2.0) + vec2<f32>(-1.0, 1.0), 0.0, 1.0);
+	out.uv = uv;
+
+	return out;
+}
+
+@fragment
+fn frag(input: VertexOutput) -> @location(0) vec4<f32> {
+	// when downsampling, average every source texel the pixel covers
+	let pixel = fwidth(input.uv);
+	let footprint = pixel * vec2<f32>(textureDimensions(source));
+	let taps = vec2<i32>(clamp(ceil(footprint - 0.01), vec2<f32>(1.0), vec2<f32>(8.0)));
+
+	var color = vec4<f32>(0.0);
+	for (var y = 0; y < taps.y; y = y + 1) {
+		for (var x = 0; x < taps.x; x = x + 1) {
+			let offset = (vec2<f32>(f32(x), f32(y)) + 0.5) / vec2<f32>(taps) - 0.5;
+			color = color + textureSampleLevel(source, source_sampler, input.uv + offset * pixel, 0.0);
+		}
+	}
+
+	return color / f32(taps.x * taps.y);
+}
@@ -0,0 +1,493 @@
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use winit::{
+    event::{
+        ElementState, Event, KeyboardInput, ModifiersState, MouseButton, VirtualKeyCode,
+        WindowEvent,
+    },
+    event_loop::ControlFlow,
+};
+
+use crate::{
+    capture::{self, Capture},
+    clock::Clock,
+    device::DeviceOptions,
+    error::Result,
+    overlay::ErrorOverlay,
+    profiler::Profiler,
+    project::Project,
+    render::{RenderOptions, Renderer},
+    upscale::Upscaler,
+    window::Window,
+};
+
+#[cfg(feature = "playback")]
+use {crate::playback::Playback, std::sync::Arc};
+
+/// Builds a [`Viewer`], see [`Viewer::builder`].
+pub struct ViewerBuilder {
+    viewer: Viewer,
+}
+
+impl ViewerBuilder {
+    /// Prefer an extended range `Rgba16Float` surface when available.
+    pub fn hdr(mut self, hdr: bool) -> Self {
+        self.viewer.hdr = hdr;
+        self
+    }
+
+    pub fn present_mode(mut self, present_mode: wgpu::PresentMode) -> Self {
+        self.viewer.present_mode = present_mode;
+        self
+    }
+
+    /// Adapter and device to request, from the manifest's `[device]` table by default.
+    pub fn device(mut self, device: DeviceOptions) -> Self {
+        self.viewer.device = Some(device);
+        self
+    }
+
+    /// Limit the frame rate to this many frames per second, values that
+    /// aren't positive leave it unlimited.
+    pub fn max_fps(mut self, max_fps: Option<f64>) -> Self {
+        self.viewer.max_fps = max_fps;
+        self
+    }
+
+    /// Stop redrawing while the window isn't focused.
+    pub fn pause_when_unfocused(mut self, pause_when_unfocused: bool) -> Self {
+        self.viewer.pause_when_unfocused = pause_when_unfocused;
+        self
+    }
+
+    /// Print a rolling average of the time spent in each pass.
+    pub fn profile(mut self, profile: bool) -> Self {
+        self.viewer.profile = profile;
+        self
+    }
+
+    /// Resolution of screenshots taken with F12, as a multiple of the window size.
+    pub fn screenshot_scale(mut self, screenshot_scale: u32) -> Self {
+        self.viewer.screenshot_scale = screenshot_scale.max(1);
+        self
+    }
+
+    pub fn build(self) -> Viewer {
+        self.viewer
+    }
+}
+
+/// Window showing a project, as `kiln show` does.
+///
+/// Space pauses, the arrow keys seek and change speed, Tab cycles through the scenes
+/// of the manifest and back to the full project, F12 saves a screenshot next to the manifest and dragging
+/// with shift and the left mouse button scrubs through the loop.
+pub struct Viewer {
+    pub project: Project,
+    pub hdr: bool,
+    pub present_mode: wgpu::PresentMode,
+    /// Adapter and device to request, from the manifest's `[device]` table if `None`.
+    pub device: Option<DeviceOptions>,
+    pub max_fps: Option<f64>,
+    pub pause_when_unfocused: bool,
+    pub profile: bool,
+    pub screenshot_scale: u32,
+}
+
+impl Viewer {
+    /// Starts building a viewer for `project`, eg.
+    ///
+    /// ```no_run
+    /// let project = kiln::Project::builder("examples/forest").load()?;
+    /// kiln::Viewer::builder(project).max_fps(Some(60.0)).build().run()?;
+    /// # Ok::<(), kiln::Error>(())
+    /// ```
+    pub fn builder(project: Project) -> ViewerBuilder {
+        ViewerBuilder {
+            viewer: Self {
+                project,
+                hdr: false,
+                present_mode: wgpu::PresentMode::Fifo,
+                device: None,
+                max_fps: None,
+                pause_when_unfocused: false,
+                profile: false,
+                screenshot_scale: 1,
+            },
+        }
+    }
+}
+
+fn screenshot_path(project: &Project) -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    let name = format!("{}-{}.png", project.manifest.project.name, millis);
+    project.path.join(name)
+}
+
+/// Starts playing the project's audio track when it changes and keeps it in sync with `clock`.
+#[cfg(feature = "playback")]
+fn sync_playback(playback: &mut Option<Playback>, project: &Project, clock: &Clock) {
+    let audio = project.audio.as_ref();
+    let clip = audio.and_then(|audio| Some((audio.clip.as_ref()?, audio.manifest.as_ref()?)));
+
+    let current = playback.as_ref().map(|playback| &playback.clip);
+    let changed = match (clip, current) {
+        (Some((clip, _)), Some(current)) => !Arc::ptr_eq(clip, current),
+        (clip, current) => clip.is_some() != current.is_some(),
+    };
+
+    if changed {
+        *playback = clip.map(|(clip, manifest)| Playback::new(clip.clone(), manifest.volume));
+    }
+
+    if let (Some(playback), Some((_, manifest))) = (playback.as_mut(), clip) {
+        if playback.volume != manifest.volume {
+            playback.set_volume(manifest.volume);
+        }
+
+        playback.sync(clock.time(), clock.realtime());
+    }
+}
+
+impl Viewer {
+    /// Opens the window and runs the viewer until it's closed, hot reloading
+    /// the project as its files change.
+    pub fn run(self) -> Result<()> {
+        let mut project = self.project;
+
+        let mut window = Window::new();
+        window.title = format!("Kiln - {}", project.manifest.project.name);
+        window.options = RenderOptions {
+            hdr: self.hdr,
+            present_mode: self.present_mode,
+            device: match self.device {
+                Some(device) => device,
+                None => DeviceOptions::from_manifest(&project.manifest.device)?,
+            },
+        };
+
+        if self.profile {
+            window.options.device.optional_features |= wgpu::Features::TIMESTAMP_QUERY;
+        }
+
+        let frame_time = self
+            .max_fps
+            .filter(|&max_fps| max_fps > 0.0)
+            .and_then(|max_fps| Duration::try_from_secs_f64(1.0 / max_fps).ok());
+        let pause_when_unfocused = self.pause_when_unfocused;
+        let mut next_frame = Instant::now();
+        let mut focused = true;
+
+        let mut upscaler: Option<Upscaler> = None;
+        let mut profiler: Option<Profiler> = None;
+        let profile = self.profile;
+        let screenshot_scale = self.screenshot_scale;
+        let mut screenshot = false;
+        let mut next_scene = false;
+        let mut cursor_x = 0.0;
+        let mut scrubbing = false;
+        let mut modifiers = ModifiersState::empty();
+
+        #[cfg(not(feature = "playback"))]
+        if project.manifest.audio.is_some() {
+            println!("kiln was built without the playback feature, audio is silent");
+        }
+
+        #[cfg(feature = "playback")]
+        let mut playback: Option<Playback> = None;
+
+        let mut clock = Clock::new();
+        let mut title = String::new();
+        let mut overlay: Option<ErrorOverlay> = None;
+        let mut manifest_error: Option<String> = None;
+        let mut check_hdr = self.hdr;
+
+        let mut draw_frame = move |renderer: &mut Renderer,
+                                   clock: &mut Clock,
+                                   screenshot: bool,
+                                   next_scene: bool|
+              -> Result<()> {
+            if std::mem::take(&mut check_hdr)
+                && renderer.config.format != wgpu::TextureFormat::Rgba16Float
+            {
+                println!("surface doesn't support Rgba16Float, hdr output disabled");
+            }
+
+            // A broken manifest keeps the last good one running until it is fixed.
+            match project.reload() {
+                Ok(true) => manifest_error = None,
+                Ok(false) => {}
+                Err(err) => {
+                    println!("{}", err);
+                    manifest_error = Some(err.to_string());
+                }
+            }
+            if next_scene {
+                project.next_scene()?;
+            }
+            project.update(&renderer.device, &renderer.queue, renderer.config.format)?;
+
+            if project.invalid {
+                if screenshot {
+                    println!("shaders failed to build, screenshot skipped");
+                }
+
+                return Ok(());
+            }
+
+            renderer.configure();
+
+            if profile && profiler.is_none() {
+                profiler = Some(Profiler::new(&renderer.device, &renderer.queue));
+            }
+
+            clock.period = project.manifest.project.r#loop;
+            let simulate = clock.tick();
+
+            #[cfg(feature = "playback")]
+            sync_playback(&mut playback, &project, clock);
+
+            let name = match project.scene {
+                Some(ref scene) => format!("{} ({})", project.manifest.project.name, scene),
+                None => project.manifest.project.name.clone(),
+            };
+            let mut clock_title = format!("Kiln - {} - {}", name, clock.status());
+            if manifest_error.is_some() {
+                clock_title.push_str(" - manifest error");
+            }
+            if clock_title != title {
+                renderer.window.set_title(&clock_title);
+                title = clock_title;
+            }
+
+            let target = renderer.surface.get_current_texture()?;
+            let target_view = target.texture.create_view(&Default::default());
+
+            let mut encoder = renderer.device.create_command_encoder(&Default::default());
+
+            let config = &renderer.config;
+            let manifest_project = &project.manifest.project;
+            let (width, height) = manifest_project.internal_size(config.width, config.height);
+
+            let time = clock.time() as f32;
+            let uniforms = project.uniforms(width, height, time);
+
+            // screenshots are read back from the upscaler when it renders at the window size
+            let window_size = (config.width, config.height);
+            let offscreen = screenshot && screenshot_scale == 1 && (width, height) == window_size;
+            if (width, height) == window_size && !offscreen {
+                upscaler = None;
+            } else {
+                let filter = manifest_project.upscale;
+                let matches = upscaler
+                    .as_ref()
+                    .is_some_and(|upscaler| upscaler.matches(width, height, config.format, filter));
+
+                if !matches {
+                    upscaler = Some(Upscaler::new(
+                        &renderer.device,
+                        width,
+                        height,
+                        config.format,
+                        filter,
+                    ));
+                }
+            }
+
+            let project_view = upscaler
+                .as_ref()
+                .map_or(&target_view, |upscaler| &upscaler.view);
+
+            // Compute passes only run when time moves, so pausing also pauses simulations.
+            project.resize(&renderer.device, width, height);
+            project.write_uniforms(&renderer.queue, &uniforms);
+            if simulate {
+                project.dispatch(&mut encoder, profiler.as_mut());
+            }
+            project.render(&mut encoder, project_view, profiler.as_mut());
+
+            if let Some(ref upscaler) = upscaler {
+                if let Some(ref mut profiler) = profiler {
+                    profiler.begin(&mut encoder, "upscale");
+                }
+
+                upscaler.blit(&mut encoder, &target_view);
+
+                if let Some(ref mut profiler) = profiler {
+                    profiler.end(&mut encoder);
+                }
+            }
+
+            if let Some(ref message) = manifest_error {
+                let matches = overlay
+                    .as_ref()
+                    .is_some_and(|overlay| overlay.format == config.format);
+                if !matches {
+                    overlay = Some(ErrorOverlay::new(&renderer.device, config.format));
+                }
+
+                if let Some(ref mut overlay) = overlay {
+                    overlay.draw(
+                        &renderer.queue,
+                        &mut encoder,
+                        &target_view,
+                        message,
+                        config.width,
+                    );
+                }
+            }
+
+            if let Some(ref mut profiler) = profiler {
+                profiler.resolve(&mut encoder);
+            }
+
+            renderer.queue.submit(std::iter::once(encoder.finish()));
+
+            target.present();
+
+            if let Some(ref mut profiler) = profiler {
+                profiler.end_frame(&renderer.device);
+            }
+
+            if screenshot {
+                let path = screenshot_path(&project);
+                let device = &renderer.device;
+                let queue = &renderer.queue;
+
+                let capture = if let (Some(upscaler), true) = (&upscaler, offscreen) {
+                    Capture::read(
+                        device,
+                        queue,
+                        &upscaler.texture,
+                        width,
+                        height,
+                        config.format,
+                    )?
+                } else {
+                    let width = config.width * screenshot_scale;
+                    let height = config.height * screenshot_scale;
+                    let target = capture::create_target(device, width, height, config.format);
+                    let target_view = target.create_view(&Default::default());
+
+                    let mut encoder = device.create_command_encoder(&Default::default());
+                    let uniforms = project.uniforms(width, height, time);
+                    project.resize(device, width, height);
+                    project.write_uniforms(queue, &uniforms);
+                    project.render(&mut encoder, &target_view, None);
+                    queue.submit(std::iter::once(encoder.finish()));
+
+                    Capture::read(device, queue, &target, width, height, config.format)?
+                };
+
+                capture.save(&path)?;
+                println!("saved screenshot to {}", path.display());
+            }
+
+            Ok(())
+        };
+
+        window.run(move |event, renderer, control_flow| match event {
+            Event::WindowEvent {
+                event: WindowEvent::Focused(is_focused),
+                ..
+            } => {
+                focused = is_focused;
+
+                if focused {
+                    renderer.request_redraw();
+                }
+            }
+            Event::WindowEvent {
+                event:
+                    WindowEvent::KeyboardInput {
+                        input:
+                            KeyboardInput {
+                                state: ElementState::Pressed,
+                                virtual_keycode: Some(key),
+                                ..
+                            },
+                        ..
+                    },
+                ..
+            } => {
+                if key == VirtualKeyCode::F12 {
+                    screenshot = true;
+                } else if key == VirtualKeyCode::Tab {
+                    next_scene = true;
+                } else if !clock.key(key) {
+                    return;
+                }
+
+                renderer.request_redraw();
+            }
+            Event::WindowEvent {
+                event:
+                    WindowEvent::MouseInput {
+                        state,
+                        button: MouseButton::Left,
+                        ..
+                    },
+                ..
+            } => {
+                // shift keeps clicks that just focus the window from jumping in time
+                scrubbing = state == ElementState::Pressed && modifiers.shift();
+
+                if scrubbing {
+                    clock.scrub(cursor_x / renderer.config.width.max(1) as f64);
+                }
+            }
+            Event::WindowEvent {
+                event: WindowEvent::ModifiersChanged(state),
+                ..
+            } => {
+                modifiers = state;
+            }
+            Event::WindowEvent {
+                event: WindowEvent::CursorMoved { position, .. },
+                ..
+            } => {
+                cursor_x = position.x;
+
+                if scrubbing {
+                    clock.scrub(cursor_x / renderer.config.width.max(1) as f64);
+                }
+            }
+            Event::RedrawRequested(_) => {
+                let screenshot = std::mem::take(&mut screenshot);
+                let next_scene = std::mem::take(&mut next_scene);
+
+                if let Err(err) = draw_frame(renderer, &mut clock, screenshot, next_scene) {
+                    println!("{}", err);
+                }
+            }
+            Event::RedrawEventsCleared => {
+                if pause_when_unfocused && !focused {
+                    return;
+                }
+
+                let frame_time = match frame_time {
+                    Some(frame_time) => frame_time,
+                    None => return renderer.request_redraw(),
+                };
+
+                let now = Instant::now();
+                if now >= next_frame {
+                    next_frame = (next_frame + frame_time).max(now);
+                    renderer.request_redraw();
+                } else if *control_flow == ControlFlow::Wait {
+                    *control_flow = ControlFlow::WaitUntil(next_frame);
+                }
+            }
+            _ => {}
+        })?;
+
+        Ok(())
+    }
+}
@@ -4,16 +4,21 @@ use winit::{
     window::WindowBuilder,
 };
 
-use crate::{error::Result, render::Renderer};
+use crate::{
+    error::Result,
+    render::{RenderOptions, Renderer},
+};
 
 pub struct Window {
     pub title: String,
+    pub options: RenderOptions,
 }
 
 impl Default for Window {
     fn default() -> Self {
         Self {
             title: String::from("kiln window"),
+            options: RenderOptions::default(),
         }
     }
 }
@@ -23,19 +28,22 @@ impl Window {
         Self::default()
     }
 
-    pub fn run(self, mut f: impl FnMut(Event<()>, &mut Renderer) + 'static) -> Result<()> {
+    pub fn run(
+        self,
+        mut f: impl FnMut(Event<()>, &mut Renderer, &mut ControlFlow) + 'static,
+    ) -> Result<()> {
         let event_loop = EventLoop::new();
         let window = WindowBuilder::new()
             .with_title(self.title)
             .build(&event_loop)?;
 
-        let mut renderer = unsafe { Renderer::new(window) };
+        let mut renderer = unsafe { Renderer::new(window, &self.options)? };
 
         event_loop.run(move |event, _, control_flow| {
             *control_flow = ControlFlow::Wait;
 
-            match event {
-                Event::WindowEvent { ref event, .. } => match event {
+            if let Event::WindowEvent { ref event, .. } = event {
+                match event {
                     WindowEvent::CloseRequested => {
                         *control_flow = ControlFlow::Exit;
                     }
@@ -50,11 +58,10 @@ impl Window {
                         renderer.should_configure = true;
                     }
                     _ => {}
-                },
-                _ => {}
+                }
             }
 
-            f(event, &mut renderer);
+            f(event, &mut renderer, control_flow);
         });
     }
 }